@@ -0,0 +1,98 @@
+//! `apropos`/`whatis`-style lookups over page names and NAME-line
+//! descriptions, printed the way man-db prints them. Only man pages are
+//! listed, not tldr pages or info nodes.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use regex::Regex;
+
+use crate::doc::is_man_page;
+use crate::graph::split_page;
+use crate::index::MmapIndex;
+
+/// Width man-db pads `name (section)` to before the description.
+const NAME_COLUMN: usize = 20;
+
+pub struct AproposEntry {
+    pub doc_id: u32,
+    pub name: String,
+    pub section: String,
+    pub description: String,
+}
+
+impl fmt::Display for AproposEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let page = format!("{} ({})", self.name, self.section);
+        write!(f, "{page:<NAME_COLUMN$} - {}", self.description)
+    }
+}
+
+/// True if `section` is wanted by a `-s` list such as `1:8` or `3,5`.
+/// A listed section also covers its suffixed variants (`3` matches `3p`).
+pub fn section_matches(section: &str, wanted: &[String]) -> bool {
+    wanted.is_empty()
+        || wanted
+            .iter()
+            .any(|w| section.to_lowercase().starts_with(&w.to_lowercase()))
+}
+
+fn entry(index: &MmapIndex, doc_id: u32) -> Option<AproposEntry> {
+    let fname = &index.doc_map[doc_id as usize];
+    let (name, section) = split_page(fname).filter(|_| is_man_page(fname))?;
+    Some(AproposEntry {
+        doc_id,
+        name: name.to_string(),
+        section: section.to_string(),
+        description: index.name_descs[doc_id as usize].clone(),
+    })
+}
+
+/// `whatis`: pages named exactly `name` (case-insensitive), best section first.
+pub fn whatis(index: &MmapIndex, name: &str, sections: &[String]) -> Vec<AproposEntry> {
+    let mut found: Vec<AproposEntry> = (0..index.doc_map.len() as u32)
+        .filter_map(|doc_id| entry(index, doc_id))
+        .filter(|e| e.name.eq_ignore_ascii_case(name) && section_matches(&e.section, sections))
+        .collect();
+    found.sort_by(|a, b| by_prior(index, a, b));
+    found
+}
+
+/// `apropos`: pages whose name or description matches any of `patterns`.
+/// Pages whose whole name matches come first, then partial name matches,
+/// then description-only matches; ties go to the page's ranking prior.
+pub fn apropos(index: &MmapIndex, patterns: &[Regex], sections: &[String]) -> Vec<AproposEntry> {
+    let mut found: Vec<(u8, AproposEntry)> = Vec::new();
+    for doc_id in 0..index.doc_map.len() as u32 {
+        let Some(e) = entry(index, doc_id) else {
+            continue;
+        };
+        if !section_matches(&e.section, sections) {
+            continue;
+        }
+        let tier = patterns
+            .iter()
+            .map(|re| match re.find(&e.name) {
+                Some(m) if m.len() == e.name.len() => 3,
+                Some(_) => 2,
+                None if re.is_match(&e.description) => 1,
+                None => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        if tier > 0 {
+            found.push((tier, e));
+        }
+    }
+    found.sort_by(|(ta, a), (tb, b)| tb.cmp(ta).then_with(|| by_prior(index, a, b)));
+    found.into_iter().map(|(_, e)| e).collect()
+}
+
+fn by_prior(index: &MmapIndex, a: &AproposEntry, b: &AproposEntry) -> Ordering {
+    index
+        .doc_prior(b.doc_id)
+        .partial_cmp(&index.doc_prior(a.doc_id))
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.section.cmp(&b.section))
+}
@@ -0,0 +1,179 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+// Files compiled into the binary, keyed by their request path (no leading '/').
+const EMBEDDED: &[(&str, &[u8])] = &[("index.html", include_bytes!("../static/index.html"))];
+
+/// Assets smaller than this are served uncompressed.
+const COMPRESS_MIN_LEN: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    /// Value for the `Content-Encoding` header, if any.
+    pub fn header_value(self) -> Option<&'static str> {
+        match self {
+            Encoding::Identity => None,
+            Encoding::Gzip => Some("gzip"),
+            Encoding::Brotli => Some("br"),
+        }
+    }
+}
+
+/// A static file with its pre-compressed variants.
+pub struct Asset {
+    pub content_type: &'static str,
+    pub etag: String,
+    raw: Vec<u8>,
+    gzip: Option<Vec<u8>>,
+    brotli: Option<Vec<u8>>,
+}
+
+impl Asset {
+    fn new(path: &str, raw: Vec<u8>) -> io::Result<Self> {
+        let mut hasher = DefaultHasher::new();
+        raw.hash(&mut hasher);
+        let etag = format!("\"{:016x}\"", hasher.finish());
+
+        let compressible = raw.len() >= COMPRESS_MIN_LEN && is_compressible(path);
+        let gzip = if compressible { Some(gzip(&raw)?) } else { None };
+        let brotli = if compressible { Some(brotli(&raw)?) } else { None };
+
+        Ok(Asset {
+            content_type: content_type_for(path),
+            etag,
+            raw,
+            gzip,
+            brotli,
+        })
+    }
+
+    /// Picks the smallest body the client accepts, based on `Accept-Encoding`.
+    pub fn body_for(&self, accept_encoding: &str) -> (Encoding, &[u8]) {
+        let accepts = |name: &str| {
+            accept_encoding.split(',').any(|part| {
+                let mut it = part.split(';');
+                let coding = it.next().unwrap_or("").trim();
+                let rejected = it.any(|p| {
+                    p.trim().strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0)
+                });
+                coding.eq_ignore_ascii_case(name) && !rejected
+            })
+        };
+
+        if let Some(br) = &self.brotli {
+            if accepts("br") {
+                return (Encoding::Brotli, br);
+            }
+        }
+        if let Some(gz) = &self.gzip {
+            if accepts("gzip") {
+                return (Encoding::Gzip, gz);
+            }
+        }
+        (Encoding::Identity, &self.raw)
+    }
+
+    /// `Cache-Control` value: HTML must always be revalidated, other assets may be cached briefly.
+    pub fn cache_control(&self) -> &'static str {
+        if self.content_type.starts_with("text/html") {
+            "no-cache"
+        } else {
+            "public, max-age=3600"
+        }
+    }
+}
+
+/// Static frontend files, either embedded at compile time or read from an
+/// override directory on every request (for frontend development).
+pub struct AssetStore {
+    embedded: HashMap<&'static str, Arc<Asset>>,
+    override_dir: Option<PathBuf>,
+}
+
+impl AssetStore {
+    /// Compresses every embedded asset once, up front.
+    pub fn new(override_dir: Option<PathBuf>) -> io::Result<Self> {
+        let mut embedded = HashMap::with_capacity(EMBEDDED.len());
+        for &(path, bytes) in EMBEDDED {
+            embedded.insert(path, Arc::new(Asset::new(path, bytes.to_vec())?));
+        }
+        Ok(AssetStore {
+            embedded,
+            override_dir,
+        })
+    }
+
+    /// Looks up `path` (e.g. "index.html"), preferring the override directory.
+    pub fn get(&self, path: &str) -> Option<Arc<Asset>> {
+        let path = path.trim_start_matches('/');
+        let path = if path.is_empty() { "index.html" } else { path };
+
+        if let Some(dir) = &self.override_dir {
+            if let Some(rel) = sanitize_asset_path(path) {
+                if let Ok(bytes) = fs::read(dir.join(rel)) {
+                    return Asset::new(path, bytes).ok().map(Arc::new);
+                }
+            }
+        }
+        self.embedded.get(path).cloned()
+    }
+}
+
+/// Rejects absolute paths and `..` so the override directory cannot be escaped.
+fn sanitize_asset_path(path: &str) -> Option<&Path> {
+    let p = Path::new(path);
+    if p.components().all(|c| matches!(c, Component::Normal(_))) {
+        Some(p)
+    } else {
+        None
+    }
+}
+
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or("") {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "ico" => "image/x-icon",
+        "woff2" => "font/woff2",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn is_compressible(path: &str) -> bool {
+    let ct = content_type_for(path);
+    ct.starts_with("text/") || ct == "application/json" || ct == "image/svg+xml"
+}
+
+fn gzip(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::best());
+    enc.write_all(raw)?;
+    enc.finish()
+}
+
+fn brotli(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    {
+        let mut enc = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+        enc.write_all(raw)?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::constants::*;
+
+/// Term -> [(related term, NPMI)], strongest first.
+pub type Associations = HashMap<String, Vec<(String, f32)>>;
+
+/// Mines term associations from per-document term sets (NAME description,
+/// synopsis and command name) using normalised pointwise mutual information:
+///
+///   npmi(a, b) = ln(p(a,b) / (p(a) p(b))) / -ln p(a,b)
+///
+/// which is 1.0 for terms that only ever appear together and 0.0 for
+/// independent ones. Only pairs seen together in at least
+/// `ASSOC_MIN_COOCCUR` documents are kept, and at most `ASSOC_TOP_K`
+/// related terms per term.
+pub fn mine_associations(doc_terms: &[Vec<String>]) -> Associations {
+    let n = doc_terms.len().max(1) as f32;
+
+    let mut df: HashMap<&str, u32> = HashMap::new();
+    for terms in doc_terms {
+        for t in terms.iter().collect::<HashSet<_>>() {
+            *df.entry(t).or_insert(0) += 1;
+        }
+    }
+
+    // Intern frequent-enough terms so pair counting works on integers
+    let mut vocab: Vec<&str> = df
+        .iter()
+        .filter(|&(_, &c)| c >= ASSOC_MIN_COOCCUR)
+        .map(|(&t, _)| t)
+        .collect();
+    vocab.sort_unstable();
+    let ids: HashMap<&str, u32> = vocab
+        .iter()
+        .enumerate()
+        .map(|(i, &t)| (t, i as u32))
+        .collect();
+
+    let mut cooccur: HashMap<(u32, u32), u32> = HashMap::new();
+    for terms in doc_terms {
+        // Keep the caller's order (most important terms first) when capping
+        let mut seen = HashSet::new();
+        let doc_ids: Vec<u32> = terms
+            .iter()
+            .filter_map(|t| ids.get(t.as_str()).copied())
+            .filter(|&id| seen.insert(id))
+            .take(ASSOC_MAX_DOC_TERMS)
+            .collect();
+        for (i, &a) in doc_ids.iter().enumerate() {
+            for &b in &doc_ids[i + 1..] {
+                *cooccur.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut assoc: HashMap<u32, Vec<(u32, f32)>> = HashMap::new();
+    for (&(a, b), &c) in &cooccur {
+        if c < ASSOC_MIN_COOCCUR {
+            continue;
+        }
+        let p_ab = c as f32 / n;
+        let p_a = df[vocab[a as usize]] as f32 / n;
+        let p_b = df[vocab[b as usize]] as f32 / n;
+        if p_ab >= 1.0 {
+            continue;
+        }
+        let npmi = (p_ab / (p_a * p_b)).ln() / -p_ab.ln();
+        if npmi < ASSOC_MIN_NPMI {
+            continue;
+        }
+        assoc.entry(a).or_default().push((b, npmi));
+        assoc.entry(b).or_default().push((a, npmi));
+    }
+
+    assoc
+        .into_iter()
+        .map(|(term, mut related)| {
+            related.sort_by(|x, y| {
+                y.1.partial_cmp(&x.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(x.0.cmp(&y.0))
+            });
+            related.truncate(ASSOC_TOP_K);
+            let related = related
+                .into_iter()
+                .map(|(r, w)| (vocab[r as usize].to_string(), w))
+                .collect();
+            (vocab[term as usize].to_string(), related)
+        })
+        .collect()
+}
+
+/// Cosine similarity between two sparse term-weight vectors.
+pub fn cosine(a: &HashMap<String, f32>, b: &HashMap<String, f32>) -> f32 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let dot: f32 = small
+        .iter()
+        .filter_map(|(t, &w)| large.get(t).map(|&v| w * v))
+        .sum();
+    if dot == 0.0 {
+        return 0.0;
+    }
+    let norm = |v: &HashMap<String, f32>| v.values().map(|w| w * w).sum::<f32>().sqrt();
+    dot / (norm(a) * norm(b))
+}
+
+/// Adds each term's associations to `vec`, scaled by the term's own weight
+/// and `ASSOC_EXPANSION_WEIGHT`, keeping the larger weight for terms already present.
+pub fn expand_vector(vec: &mut HashMap<String, f32>, assoc: &Associations) {
+    let originals: Vec<(String, f32)> = vec.iter().map(|(t, &w)| (t.clone(), w)).collect();
+    for (term, weight) in originals {
+        for (related, npmi) in assoc.get(&term).map(Vec::as_slice).unwrap_or(&[]) {
+            let w = weight * npmi * ASSOC_EXPANSION_WEIGHT;
+            let entry = vec.entry(related.clone()).or_insert(0.0);
+            if *entry < w {
+                *entry = w;
+            }
+        }
+    }
+}
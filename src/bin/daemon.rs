@@ -0,0 +1,140 @@
+//! `man_search daemon`
+//!
+//! Keeps the index loaded and answers newline-delimited JSON-RPC 2.0
+//! requests (search, suggest, get-page, explain-cmd; see `man_search::rpc`)
+//! on a Unix socket, or on stdin/stdout for editors that spawn it directly.
+//!
+//! Usage:
+//!   cargo run --bin daemon                       # $XDG_RUNTIME_DIR/man_search.sock
+//!                                                # or /tmp/man_search-$USER/man_search.sock
+//!   cargo run --bin daemon -- --socket /tmp/ms.sock
+//!   cargo run --bin daemon -- --stdio
+//!
+//! Query it from a shell:
+//!   echo '{"jsonrpc":"2.0","id":1,"method":"search","params":{"query":"copy file"}}' \
+//!     | nc -U "$XDG_RUNTIME_DIR/man_search.sock"
+
+use std::fs;
+use std::io::{self, BufReader};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use man_search::constants::{DAEMON_SOCKET_DIR, DAEMON_SOCKET_NAME, FINAL_INDEX_PATH};
+use man_search::index::load_index;
+use man_search::rpc::RpcService;
+
+fn usage() -> io::Error {
+    eprintln!("Usage: daemon [--index <path>] [--socket <path> | --stdio]");
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+fn default_socket() -> io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(dir).join(DAEMON_SOCKET_NAME));
+    }
+    // The temp dir is shared, so the socket goes in a directory only we can
+    // enter: it exists between bind and chmod
+    let user = std::env::var("USER").unwrap_or_default();
+    let dir = std::env::temp_dir().join(format!("{DAEMON_SOCKET_DIR}-{user}"));
+    if let Err(e) = fs::DirBuilder::new().mode(0o700).create(&dir) {
+        if e.kind() != io::ErrorKind::AlreadyExists {
+            return Err(e);
+        }
+    }
+    // Someone else's directory is either not private or not writable by us
+    let meta = fs::symlink_metadata(&dir)?;
+    if !meta.is_dir() || meta.permissions().mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(dir.join(DAEMON_SOCKET_NAME))
+}
+
+/// Binds `path`, replacing a socket file left behind by a daemon that is
+/// no longer running.
+fn bind(path: &PathBuf) -> io::Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", path.display()),
+            ));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    // Queries can reveal what the user is working on
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut socket = None;
+    let mut stdio = false;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--socket" => socket = Some(it.next().map(PathBuf::from).ok_or_else(usage)?),
+            "--stdio" => stdio = true,
+            _ => return Err(usage()),
+        }
+    }
+
+    eprint!("Loading index '{index_path}'… ");
+    let index = load_index(&index_path).map_err(|e| {
+        eprintln!("\nFailed to load index: {e}");
+        e
+    })?;
+    eprintln!("OK ({} docs)", index.doc_map.len());
+    let service = Arc::new(RpcService::new(index));
+
+    if stdio {
+        return service.serve(io::stdin().lock(), io::stdout().lock());
+    }
+
+    let socket = match socket {
+        Some(socket) => socket,
+        None => default_socket().map_err(|e| {
+            eprintln!("Cannot create the socket directory: {e}");
+            e
+        })?,
+    };
+    let listener = bind(&socket).map_err(|e| {
+        eprintln!("Cannot listen on {}: {e}", socket.display());
+        e
+    })?;
+    eprintln!("Listening on {}", socket.display());
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Connection failed: {e}");
+                continue;
+            }
+        };
+        let service = Arc::clone(&service);
+        thread::spawn(move || {
+            let reader = match stream.try_clone() {
+                Ok(s) => BufReader::new(s),
+                Err(e) => {
+                    eprintln!("Connection failed: {e}");
+                    return;
+                }
+            };
+            // A client hanging up mid-reply is not worth reporting
+            let _ = service.serve(reader, stream);
+        });
+    }
+    Ok(())
+}
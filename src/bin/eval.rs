@@ -0,0 +1,218 @@
+//! `man_search eval`
+//!
+//! Scores the ranker against a judged query set and reports MRR, nDCG@10
+//! and recall@k per query and overall.
+//!
+//! Judgments file, one line per relevant page (`#` starts a comment):
+//!   copy a directory<TAB>cp.1<TAB>3
+//!   copy a directory<TAB>rsync<TAB>1
+//!
+//! Usage:
+//!   cargo run --bin eval -- judgments.tsv
+//!   cargo run --bin eval -- --index custom.idx --k 20 judgments.tsv
+//!
+//! Diff mode (compare two indexes or two ranking profiles side by side):
+//!   cargo run --bin eval -- --index old.idx --compare new.idx judgments.tsv
+//!   cargo run --bin eval -- --profile a.json --compare-profile b.json judgments.tsv
+
+use std::io;
+use std::path::Path;
+
+use man_search::constants::{FINAL_INDEX_PATH, RANKING_PROFILE_PATH};
+use man_search::eval::{evaluate_index, load_judgments, QueryJudgments, QueryMetrics};
+use man_search::index::{load_index, MmapIndex};
+use man_search::profile::RankingProfile;
+
+const DEFAULT_RECALL_K: usize = 10;
+
+fn usage() -> io::Error {
+    eprintln!(
+        "Usage: eval [--index <path>] [--profile <path>] [--compare <path>] \
+         [--compare-profile <path>] [--k <n>] <judgments.tsv>"
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+fn load(path: &str) -> io::Result<MmapIndex> {
+    eprint!("Loading index '{path}'… ");
+    let index = load_index(path).map_err(|e| {
+        eprintln!("\nFailed to load index: {e}");
+        e
+    })?;
+    eprintln!("OK ({} docs)", index.doc_map.len());
+    Ok(index)
+}
+
+/// Human-readable name for an index/profile pair.
+fn label(index_path: &str, profile_path: Option<&str>) -> String {
+    match profile_path {
+        Some(profile) => format!("{index_path} with {profile}"),
+        None => index_path.to_string(),
+    }
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let cut: String = s.chars().take(width - 1).collect();
+        format!("{cut}…")
+    }
+}
+
+fn print_single(judgments: &[QueryJudgments], metrics: &[QueryMetrics], k: usize) {
+    println!(
+        "{:<40} {:>6} {:>8} {:>6}",
+        "query",
+        "RR",
+        "nDCG@10",
+        format!("R@{k}")
+    );
+    println!("{}", "─".repeat(63));
+    for (j, m) in judgments.iter().zip(metrics) {
+        println!(
+            "{:<40} {:>6.3} {:>8.3} {:>6.3}",
+            truncate(&j.query, 40),
+            m.rr,
+            m.ndcg,
+            m.recall
+        );
+    }
+    let mean = QueryMetrics::mean(metrics);
+    println!("{}", "─".repeat(63));
+    println!(
+        "{:<40} {:>6.3} {:>8.3} {:>6.3}",
+        format!("overall ({} queries)", metrics.len()),
+        mean.rr,
+        mean.ndcg,
+        mean.recall
+    );
+}
+
+fn print_diff(judgments: &[QueryJudgments], a: &[QueryMetrics], b: &[QueryMetrics], k: usize) {
+    println!(
+        "{:<32} {:>15} {:>15} {:>15}",
+        "query",
+        "RR a→b",
+        "nDCG@10 a→b",
+        format!("R@{k} a→b")
+    );
+    println!("{}", "─".repeat(80));
+
+    let (mut wins, mut losses) = (0, 0);
+    for ((j, ma), mb) in judgments.iter().zip(a).zip(b) {
+        let delta = mb.ndcg - ma.ndcg;
+        let mark = if delta > 1e-4 {
+            wins += 1;
+            "+"
+        } else if delta < -1e-4 {
+            losses += 1;
+            "-"
+        } else {
+            " "
+        };
+        println!(
+            "{mark}{:<31} {:>6.3}→{:<8.3} {:>6.3}→{:<8.3} {:>6.3}→{:<8.3}",
+            truncate(&j.query, 31),
+            ma.rr,
+            mb.rr,
+            ma.ndcg,
+            mb.ndcg,
+            ma.recall,
+            mb.recall
+        );
+    }
+
+    let (ma, mb) = (QueryMetrics::mean(a), QueryMetrics::mean(b));
+    println!("{}", "─".repeat(80));
+    println!(
+        " {:<31} {:>6.3}→{:<8.3} {:>6.3}→{:<8.3} {:>6.3}→{:<8.3}",
+        format!("overall ({} queries)", a.len()),
+        ma.rr,
+        mb.rr,
+        ma.ndcg,
+        mb.ndcg,
+        ma.recall,
+        mb.recall
+    );
+    println!(
+        "\nΔ MRR {:+.3}   Δ nDCG@10 {:+.3}   Δ R@{k} {:+.3}   ({wins} better, {losses} worse by nDCG@10)",
+        mb.rr - ma.rr,
+        mb.ndcg - ma.ndcg,
+        mb.recall - ma.recall
+    );
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut profile_path: Option<String> = None;
+    let mut compare_path: Option<String> = None;
+    let mut compare_profile: Option<String> = None;
+    let mut k = DEFAULT_RECALL_K;
+    let mut judgments_path: Option<String> = None;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--profile" => profile_path = Some(it.next().ok_or_else(usage)?),
+            "--compare" => compare_path = Some(it.next().ok_or_else(usage)?),
+            "--compare-profile" => compare_profile = Some(it.next().ok_or_else(usage)?),
+            "--k" => {
+                k = it
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&v| v > 0)
+                    .ok_or_else(usage)?
+            }
+            _ if judgments_path.is_none() => judgments_path = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+    let judgments_path = judgments_path.ok_or_else(usage)?;
+
+    let judgments = load_judgments(&judgments_path)?;
+    if judgments.is_empty() {
+        eprintln!("No judgments in '{judgments_path}'");
+        return Ok(());
+    }
+
+    let mut index = load(&index_path)?;
+    if let Some(path) = &profile_path {
+        index.set_profile(RankingProfile::read(path)?);
+    }
+    let metrics = evaluate_index(&judgments, &index, k);
+
+    if compare_path.is_none() && compare_profile.is_none() {
+        print_single(&judgments, &metrics, k);
+        return Ok(());
+    }
+
+    let other_path = compare_path.unwrap_or_else(|| index_path.clone());
+    let mut other = if other_path == index_path {
+        index
+    } else {
+        drop(index);
+        load(&other_path)?
+    };
+    // Without --compare-profile both sides use the same profile
+    let other_profile = compare_profile.or(profile_path.clone());
+    other.set_profile(match &other_profile {
+        Some(path) => RankingProfile::read(path)?,
+        None => {
+            RankingProfile::load(&Path::new(&other_path).with_file_name(RANKING_PROFILE_PATH))
+        }
+    });
+    let other_metrics = evaluate_index(&judgments, &other, k);
+
+    println!(
+        "a = {}\nb = {}\n",
+        label(&index_path, profile_path.as_deref()),
+        label(&other_path, other_profile.as_deref())
+    );
+    print_diff(&judgments, &metrics, &other_metrics, k);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! `man_search export`
+//!
+//! Dumps an index to JSON Lines or an SQLite database (see
+//! `man_search::export` for the records and tables) so the corpus can be
+//! analysed without parsing the binary format. `import` reads it back.
+//!
+//! Usage:
+//!   cargo run --bin export -- docs.jsonl                   # documents only
+//!   cargo run --bin export -- --postings --text - | jq .   # everything, to stdout
+//!   cargo run --features sqlite --bin export -- --postings man.db
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+
+use man_search::constants::FINAL_INDEX_PATH;
+use man_search::export::{write_jsonl, ExportFormat, ExportOptions};
+use man_search::index::{load_index, MmapIndex};
+
+fn usage() -> io::Error {
+    eprintln!(
+        "Usage: export [--index <path>] [--format jsonl|sqlite] [--postings] [--text] <out|->"
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+#[cfg(feature = "sqlite")]
+fn write_sqlite(index: &MmapIndex, options: ExportOptions, path: &str) -> io::Result<()> {
+    man_search::export::sqlite::write_sqlite(index, options, path)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn write_sqlite(_: &MmapIndex, _: ExportOptions, _: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SQLite export needs a build with `--features sqlite`",
+    ))
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut format: Option<ExportFormat> = None;
+    let mut options = ExportOptions::default();
+    let mut out: Option<String> = None;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--format" => {
+                format = Some(it.next().ok_or_else(usage)?.parse().map_err(|e: String| {
+                    eprintln!("{e}");
+                    usage()
+                })?)
+            }
+            "--postings" => options.postings = true,
+            "--text" => options.text = true,
+            _ if out.is_none() => out = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+    let out = out.ok_or_else(usage)?;
+    let format = format.unwrap_or_else(|| ExportFormat::from_path(&out));
+
+    eprint!("Loading index '{index_path}'… ");
+    let index = load_index(&index_path).map_err(|e| {
+        eprintln!("\nFailed to load index: {e}");
+        e
+    })?;
+    eprintln!("OK ({} docs)", index.doc_map.len());
+
+    match format {
+        ExportFormat::Jsonl if out == "-" => write_jsonl(&index, options, io::stdout().lock()),
+        ExportFormat::Jsonl => write_jsonl(&index, options, BufWriter::new(File::create(&out)?)),
+        ExportFormat::Sqlite => write_sqlite(&index, options, &out),
+    }
+    .map_err(|e| {
+        eprintln!("Export failed: {e}");
+        e
+    })?;
+    if out != "-" {
+        eprintln!("Wrote '{out}'");
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! `man_search import`
+//!
+//! Builds an index from an `export` dump taken with `--postings`, possibly
+//! filtered or edited in between. Page texts come along when the dump was
+//! taken with `--text`; embeddings are not carried over.
+//!
+//! Link authority is not recomputed: every page keeps the score in the dump,
+//! computed by `index` over all pages, including inline mentions that are
+//! not exported. Dropping pages leaves the others' authority as it was;
+//! re-run `index` to recompute it.
+//!
+//! Usage:
+//!   cargo run --bin import -- dump.jsonl                   # writes temp-man.idx
+//!   cargo run --features sqlite --bin import -- --out man.idx man.db
+
+use std::fs::File;
+use std::io::{self, BufReader};
+
+use man_search::constants::FINAL_INDEX_PATH;
+use man_search::export::{build_from_export, read_jsonl, Export, ExportFormat};
+use man_search::index::save_index;
+
+fn usage() -> io::Error {
+    eprintln!("Usage: import [--format jsonl|sqlite] [--out <path>] <dump|->");
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+#[cfg(feature = "sqlite")]
+fn read_sqlite(path: &str) -> io::Result<Export> {
+    man_search::export::sqlite::read_sqlite(path)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn read_sqlite(_: &str) -> io::Result<Export> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "SQLite import needs a build with `--features sqlite`",
+    ))
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut out = FINAL_INDEX_PATH.to_string();
+    let mut format: Option<ExportFormat> = None;
+    let mut input: Option<String> = None;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--out" => out = it.next().ok_or_else(usage)?,
+            "--format" => {
+                format = Some(it.next().ok_or_else(usage)?.parse().map_err(|e: String| {
+                    eprintln!("{e}");
+                    usage()
+                })?)
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(usage()),
+        }
+    }
+    let input = input.ok_or_else(usage)?;
+    let format = format.unwrap_or_else(|| ExportFormat::from_path(&input));
+
+    let export = match format {
+        ExportFormat::Jsonl if input == "-" => read_jsonl(io::stdin().lock()),
+        ExportFormat::Jsonl => read_jsonl(BufReader::new(File::open(&input)?)),
+        ExportFormat::Sqlite => read_sqlite(&input),
+    }
+    .map_err(|e| {
+        eprintln!("Cannot read '{input}': {e}");
+        e
+    })?;
+    println!(
+        "Read {} docs, {} terms, {} associated terms",
+        export.docs.len(),
+        export.terms.len(),
+        export.associations.len()
+    );
+
+    let index = build_from_export(export).map_err(|e| {
+        eprintln!("Cannot build an index: {e}");
+        e
+    })?;
+    save_index(&out, &index)?;
+    println!("Saved index to '{out}'");
+    Ok(())
+}
@@ -3,11 +3,63 @@ use std::io;
 
 use man_search::constants::{FINAL_INDEX_PATH, SOURCE_DIRS, TEMP_INDEX_PATH};
 use man_search::crawl::crawl;
+#[cfg(feature = "embeddings")]
+use man_search::constants::EMBEDDING_MODEL_PATH;
+#[cfg(feature = "embeddings")]
+use man_search::embed::{embed_index, WordVectors};
+#[cfg(feature = "embeddings")]
+use man_search::index::Index;
 use man_search::index::{build_index, save_index};
 
+/// Adds dense page vectors when a word-vector model is available.
+#[cfg(feature = "embeddings")]
+fn with_embeddings(mut index: Index) -> Index {
+    match WordVectors::load(EMBEDDING_MODEL_PATH) {
+        Ok(model) => {
+            let vectors = embed_index(&index, &model);
+            println!(
+                "      {}-d embeddings from '{EMBEDDING_MODEL_PATH}'  |  {} stems with vectors",
+                vectors.dim,
+                vectors.terms.len()
+            );
+            index.vectors = Some(vectors);
+        }
+        Err(e) => println!("      skipping embeddings ('{EMBEDDING_MODEL_PATH}': {e})"),
+    }
+    index
+}
+
+fn usage() -> io::Error {
+    eprintln!("Usage: index [--out <path>] [[name=]source dir...]");
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
 fn main() -> io::Result<()> {
-    println!("[1/3] Crawling {} source directories…", SOURCE_DIRS.len());
-    let stats = crawl(&SOURCE_DIRS, TEMP_INDEX_PATH)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // With directories given, e.g. a vendor's man pages, the result can be
+    // added to an existing index as a segment (see the `segments` binary).
+    // Pages are tagged with the name before `=`, or else the directory.
+    let mut out = FINAL_INDEX_PATH.to_string();
+    let mut dirs: Vec<String> = Vec::new();
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--out" => out = it.next().ok_or_else(usage)?,
+            _ if arg.starts_with('-') => return Err(usage()),
+            _ => dirs.push(arg),
+        }
+    }
+    let sources: Vec<(&str, &str)> = if dirs.is_empty() {
+        SOURCE_DIRS.to_vec()
+    } else {
+        dirs.iter()
+            .map(|arg| arg.split_once('=').unwrap_or((arg, arg)))
+            .collect()
+    };
+
+    println!("[1/3] Crawling {} source directories…", sources.len());
+    let stats = crawl(&sources, TEMP_INDEX_PATH)?;
     println!(
         "      {} docs  |  avg desc={:.1}  synopsis={:.1}  body={:.1}",
         stats.total_docs, stats.avg_desc_len, stats.avg_synopsis_len, stats.avg_body_len
@@ -16,14 +68,22 @@ fn main() -> io::Result<()> {
     println!("[2/3] Building BM25 + semantic index…");
     let index = build_index(TEMP_INDEX_PATH, &stats)?;
     println!(
-        "      {} index terms  |  {} cmd names  |  {} desc terms",
+        "      {} index terms  |  {} cmd names  |  {} desc terms  |  {} associated terms",
         index.inverted.len(),
         index.cmd_name_index.len(),
-        index.desc_index.len()
+        index.desc_index.len(),
+        index.associations.len()
+    );
+    println!(
+        "      {} cross-reference links",
+        index.links.iter().map(Vec::len).sum::<usize>()
     );
 
-    println!("[3/3] Saving index to '{FINAL_INDEX_PATH}'…");
-    save_index(FINAL_INDEX_PATH, &index)?;
+    #[cfg(feature = "embeddings")]
+    let index = with_embeddings(index);
+
+    println!("[3/3] Saving index to '{out}'…");
+    save_index(&out, &index)?;
 
     let _ = fs::remove_file(TEMP_INDEX_PATH);
     println!("Done.  Run `cargo run --bin search -- <query>` to search.");
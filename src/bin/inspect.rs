@@ -0,0 +1,240 @@
+//! `man_search inspect`
+//!
+//! Prints what is inside an index file: header and layout, document and
+//! vocabulary counts, the largest posting lists, one document's metadata,
+//! one term's posting list, and a consistency check.
+//!
+//! Usage:
+//!   cargo run --bin inspect                      # summary
+//!   cargo run --bin inspect -- --index man.idx --top-terms 50
+//!   cargo run --bin inspect -- --doc ls.1        # or a doc id: --doc 42
+//!   cargo run --bin inspect -- --term copy --limit 50
+//!   cargo run --bin inspect -- --check           # exits 1 on problems
+
+use std::collections::BTreeMap;
+use std::io;
+use std::process::ExitCode;
+
+use man_search::constants::FINAL_INDEX_PATH;
+use man_search::index::{load_index, MmapIndex};
+use man_search::inspect::{check, largest_postings, layout, posting_location};
+use man_search::text::{make_stemmer, tokenize};
+
+const DEFAULT_TOP_TERMS: usize = 20;
+const DEFAULT_POSTINGS_SHOWN: usize = 20;
+
+fn usage() -> io::Error {
+    eprintln!(
+        "Usage: inspect [--index <path>] [--top-terms <n>] [--doc <fname|id>] \
+         [--term <word> [--limit <n>]] [--check]"
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+fn parse_count(value: Option<String>) -> io::Result<usize> {
+    value.and_then(|v| v.parse().ok()).ok_or_else(usage)
+}
+
+fn print_summary(index: &MmapIndex, top_terms: usize) {
+    println!("segments         {}", index.segments().len());
+    println!("documents        {}", index.doc_map.len());
+    let mut sources: BTreeMap<&str, usize> = BTreeMap::new();
+    for source in &index.sources {
+        *sources.entry(source.as_str()).or_default() += 1;
+    }
+    let sources: Vec<String> = sources
+        .iter()
+        .map(|(source, n)| format!("{source} {n}"))
+        .collect();
+    println!("sources          {}", sources.join(", "));
+    println!("vocabulary       {} terms", index.inverted_dict.len());
+    println!("command names    {}", index.cmd_name_index.len());
+    println!("associations     {} terms", index.associations.len());
+    println!(
+        "SEE ALSO links   {}",
+        index.see_also.iter().map(Vec::len).sum::<usize>()
+    );
+    let postings: u64 = index.inverted_dict.values().map(|&n| n as u64).sum();
+    println!("postings         {postings}");
+
+    for segment in index.segments() {
+        let layout = layout(segment);
+        println!(
+            "\n{}: format version {}, {} bytes, {} of {} docs live, {} terms",
+            segment.path,
+            layout.version,
+            layout.file_size,
+            segment.live_docs(),
+            segment.doc_count(),
+            segment.vocabulary()
+        );
+        println!("{:<14} {:>12} {:>12}", "section", "offset", "bytes");
+        println!("{}", "─".repeat(40));
+        for (name, offset, size) in &layout.sections {
+            println!("{name:<14} {offset:>12} {size:>12}");
+        }
+    }
+
+    println!("\n{:<24} {:>8}", "largest posting lists", "docs");
+    println!("{}", "─".repeat(33));
+    for (term, len) in largest_postings(index, top_terms) {
+        println!("{term:<24} {len:>8}");
+    }
+}
+
+fn print_doc(index: &MmapIndex, key: &str) -> bool {
+    let doc_id = match key.parse::<u32>() {
+        Ok(id) if (id as usize) < index.doc_map.len() => Some(id),
+        _ => index.find_doc(key),
+    };
+    let Some(doc_id) = doc_id else {
+        eprintln!("No document `{key}`");
+        return false;
+    };
+    let i = doc_id as usize;
+
+    println!("doc id           {doc_id}");
+    println!("fname            {}", index.doc_map[i]);
+    println!("source           {}", index.sources[i]);
+    println!("command name     {}", index.cmd_names[i]);
+    println!("description      {}", index.name_descs[i]);
+    let [desc_len, synopsis_len, body_len] = index.field_lens[i];
+    println!("field lengths    description {desc_len}, synopsis {synopsis_len}, body {body_len}");
+    println!("authority        {:.4}", index.authority[i]);
+    println!("prior            {:.4}", index.doc_prior(doc_id));
+    println!("text             {} bytes", index.doc_text(doc_id).len());
+    let see_also: Vec<&str> = index.see_also[i]
+        .iter()
+        .map(|&t| index.doc_map[t as usize].as_str())
+        .collect();
+    println!("SEE ALSO         {}", see_also.join(", "));
+    if let Some(page) = index.example_pages[i] {
+        println!(
+            "examples         {} from {}",
+            index.examples_for(doc_id).len(),
+            index.doc_map[page as usize]
+        );
+    }
+    if let Some(page) = index.info_links[i] {
+        println!("info link        {}", index.doc_map[page as usize]);
+    }
+    let terms: Vec<String> = index.term_vectors[i]
+        .iter()
+        .map(|(term, weight)| format!("{term} {weight:.2}"))
+        .collect();
+    println!("top terms        {}", terms.join(", "));
+    true
+}
+
+fn print_term(index: &MmapIndex, word: &str, limit: usize) -> bool {
+    // Accept both index terms and ordinary words, which are stemmed
+    let term = if index.inverted_dict.contains_key(word) {
+        word.to_string()
+    } else {
+        match tokenize(word, &make_stemmer()).into_iter().next() {
+            Some(stem) => stem,
+            None => word.to_string(),
+        }
+    };
+    let Some(mut postings) = index.get_field_postings(&term) else {
+        eprintln!("`{term}` is not in the dictionary");
+        return false;
+    };
+    let len = postings.len();
+    println!("term `{term}`: {len} postings");
+    for segment in index.segments() {
+        if let Some((offset, n)) = posting_location(segment, &term) {
+            println!("  {}: {n} at byte {offset}", segment.path);
+        }
+    }
+    if let Some(related) = index.associations.get(&term) {
+        let related: Vec<String> = related
+            .iter()
+            .map(|(r, w)| format!("{r} {w:.2}"))
+            .collect();
+        println!("associated: {}", related.join(", "));
+    }
+
+    let scores = index.get_postings(&term).unwrap_or_default();
+    println!(
+        "\n{:>6} {:<28} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "doc", "fname", "cmd", "desc", "synopsis", "body", "score"
+    );
+    println!("{}", "─".repeat(82));
+    postings.truncate(limit);
+    for ((doc_id, parts), (_, score)) in postings.iter().zip(&scores) {
+        println!(
+            "{doc_id:>6} {:<28} {:>8.3} {:>8.3} {:>8.3} {:>8.3} {score:>8.3}",
+            index.doc_map[*doc_id as usize], parts[0], parts[1], parts[2], parts[3]
+        );
+    }
+    if len > limit {
+        println!("… {} more", len - limit);
+    }
+    true
+}
+
+fn print_check(index: &MmapIndex) -> bool {
+    let report = check(index);
+    for (name, findings) in &report.checks {
+        let status = if findings.count == 0 {
+            "ok".to_string()
+        } else {
+            format!("{} problems", findings.count)
+        };
+        println!("{name:<44} {status}");
+        for example in &findings.examples {
+            println!("    {example}");
+        }
+    }
+    report.is_ok()
+}
+
+fn main() -> io::Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut top_terms = DEFAULT_TOP_TERMS;
+    let mut limit = DEFAULT_POSTINGS_SHOWN;
+    let mut doc: Option<String> = None;
+    let mut term: Option<String> = None;
+    let mut run_check = false;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--top-terms" => top_terms = parse_count(it.next())?,
+            "--limit" => limit = parse_count(it.next())?,
+            "--doc" => doc = Some(it.next().ok_or_else(usage)?),
+            "--term" => term = Some(it.next().ok_or_else(usage)?),
+            "--check" => run_check = true,
+            _ => return Err(usage()),
+        }
+    }
+
+    let index = load_index(&index_path).map_err(|e| {
+        eprintln!("Failed to load index '{index_path}': {e}");
+        e
+    })?;
+
+    let mut ok = true;
+    if let Some(key) = &doc {
+        ok &= print_doc(&index, key);
+    }
+    if let Some(word) = &term {
+        ok &= print_term(&index, word, limit);
+    }
+    if run_check {
+        ok &= print_check(&index);
+    }
+    if doc.is_none() && term.is_none() && !run_check {
+        print_summary(&index, top_terms);
+    }
+
+    Ok(if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
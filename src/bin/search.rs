@@ -5,72 +5,570 @@
 //! Usage (single query):
 //!   cargo run --bin search -- "copy file"
 //!
-//! Usage (interactive REPL):
+//! Usage (interactive REPL, `:help` lists its commands):
 //!   cargo run --bin search
 //!
 //! Use a custom index:
 //!   cargo run --bin search -- --index custom.idx "copy file"
+//!
+//! Full-screen search-as-you-type UI:
+//!   cargo run --bin search -- --tui
+//!
+//! Machine-readable output (`--format text|plain|tsv|json|jsonl`, `-q` drops
+//! status lines; any format but `text` implies it):
+//!   cargo run --bin search -- --format jsonl --top-k 20 "copy file"
+//!
+//! Only pages from some sources (`source:` in the query works too):
+//!   cargo run --bin search -- --source coreutils "copy file"
+//!
+//! apropos / whatis replacements (`-s 1:8` limits sections):
+//!   cargo run --bin search -- -k '^git-'
+//!   cargo run --bin search -- -f ls
+//!   cargo run --bin search -- -s 3 -f printf
+//!
+//! Shell integration (Ctrl-G searches the command line, plus a
+//! command-not-found hook):
+//!   eval "$(search shell-init bash)"          # or zsh; fish: `| source`
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitCode, Stdio};
 
-use std::io::{self, BufRead, Write};
+use regex::RegexBuilder;
+use rustyline::config::Config;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
 
-use man_search::constants::FINAL_INDEX_PATH;
-use man_search::index::load_index;
-use man_search::search::search_and_print;
+use man_search::apropos::{apropos, whatis, AproposEntry};
+use man_search::constants::{FINAL_INDEX_PATH, REPL_HISTORY_FILE, REPL_HISTORY_SIZE};
+use man_search::doc::is_man_page;
+use man_search::graph::split_page;
+use man_search::index::{load_index, MmapIndex};
+use man_search::search::{
+    explain, search_report, write_report, OutputFormat, ReportOptions, SearchReport,
+};
+use man_search::shell::{closest_commands, init_script, Shell};
+use man_search::spell::SpellChecker;
+use man_search::tui;
 
 const DEFAULT_TOP_K: usize = 10;
+/// Exit status of man-db's `apropos`/`whatis` when nothing matched.
+const EXIT_NOTHING_FOUND: u8 = 16;
+/// Exit status shells use for a command that was not found.
+const EXIT_COMMAND_NOT_FOUND: u8 = 127;
 
-fn main() -> io::Result<()> {
+#[derive(PartialEq)]
+enum Mode {
+    Search,
+    /// `--tui`: full-screen UI, arguments are the initial query
+    Tui,
+    /// `-k`: arguments are regexes matched against names and descriptions
+    Apropos,
+    /// `-f`: arguments are exact page names
+    Whatis,
+    /// `--not-found`: the argument is a command the shell could not find
+    NotFound,
+}
+
+fn usage() -> io::Error {
+    eprintln!(
+        "Usage: search [--index <path>] [--format text|plain|tsv|json|jsonl] [--top-k <n>] [-q] \\
+         [-s <sections>] [--source <names>] [query...]"
+    );
+    eprintln!("       search [--index <path>] [-s <sections>] [--source <names>] --tui [query...]");
+    eprintln!("       search [--index <path>] [-s <sections>] -k <regex>...");
+    eprintln!("       search [--index <path>] [-s <sections>] -f <name>...");
+    eprintln!("       search shell-init [--index <path>] bash|zsh|fish");
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+fn main() -> io::Result<ExitCode> {
     let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|a| a == "shell-init") {
+        return shell_init(&args[1..]);
+    }
 
-    // Parse optional --index <path> flag
-    let (index_path, query_args): (&str, Vec<&str>) = {
-        let mut idx = FINAL_INDEX_PATH;
-        let mut rest: Vec<&str> = Vec::new();
-        let mut skip = false;
-        for (i, arg) in args.iter().enumerate() {
-            if skip {
-                skip = false;
-                continue;
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut mode = Mode::Search;
+    let mut sections: Vec<String> = Vec::new();
+    let mut sources: Vec<String> = Vec::new();
+    let mut query_args: Vec<String> = Vec::new();
+    let mut format = OutputFormat::Text;
+    let mut top_k = DEFAULT_TOP_K;
+    let mut quiet = false;
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--format" => {
+                format = it.next().ok_or_else(usage)?.parse().map_err(|e: String| {
+                    eprintln!("{e}");
+                    usage()
+                })?
             }
-            if arg == "--index" {
-                idx = args.get(i + 1).map(|s| s.as_str()).unwrap_or(idx);
-                skip = true;
-            } else {
-                rest.push(arg.as_str());
+            "-n" | "--top-k" => top_k = it.next().and_then(|v| v.parse().ok()).ok_or_else(usage)?,
+            "-q" | "--quiet" => quiet = true,
+            "-t" | "--tui" => mode = Mode::Tui,
+            "-k" | "--apropos" => mode = Mode::Apropos,
+            "-f" | "--whatis" => mode = Mode::Whatis,
+            "--not-found" => mode = Mode::NotFound,
+            "-s" | "--sections" => {
+                let list = it.next().ok_or_else(usage)?;
+                sections.extend(
+                    list.split([':', ','])
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string),
+                );
             }
+            "--source" => {
+                let list = it.next().ok_or_else(usage)?;
+                sources.extend(list.split(',').filter(|s| !s.is_empty()).map(str::to_string));
+            }
+            _ => query_args.push(arg),
         }
-        (idx, rest)
+    }
+    if matches!(mode, Mode::Apropos | Mode::Whatis) && query_args.is_empty() {
+        let tool = if mode == Mode::Apropos {
+            "apropos"
+        } else {
+            "whatis"
+        };
+        eprintln!("{tool} what?");
+        return Err(usage());
+    }
+    let options = ReportOptions {
+        top_k,
+        offset: 0,
+        sections: sections.clone(),
+        sources,
     };
+    // Status lines would end up mixed into piped output
+    quiet |= format != OutputFormat::Text || mode != Mode::Search;
 
     // ── Load index ──────────────────────────────────────────────────────────
-    eprint!("Loading index '{index_path}'… ");
-    let index = load_index(index_path).map_err(|e| {
+    if !quiet {
+        eprint!("Loading index '{index_path}'… ");
+    }
+    if mode == Mode::NotFound {
+        let typed = query_args.first().ok_or_else(usage)?;
+        // The hook must still report the missing command without an index
+        if let Ok(index) = load_index(&index_path) {
+            print_not_found(&index, typed);
+        } else {
+            eprintln!("{typed}: command not found");
+        }
+        return Ok(ExitCode::from(EXIT_COMMAND_NOT_FOUND));
+    }
+    let index = load_index(&index_path).map_err(|e| {
         eprintln!("\nFailed to load index: {e}");
-        eprintln!("Have you run `cargo run --bin index` first?");
+        if e.kind() == io::ErrorKind::NotFound {
+            eprintln!("Have you run `cargo run --bin index` first?");
+        }
         e
     })?;
-    eprintln!("OK ({} docs)", index.doc_map.len());
+    if !quiet {
+        eprintln!("OK ({} docs)", index.doc_map.len());
+    }
+
+    if mode == Mode::Tui {
+        tui::run(&index, &query_args.join(" "), &options)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // ── apropos / whatis ────────────────────────────────────────────────────
+    if mode != Mode::Search {
+        let mut found = false;
+        let mut print = |keyword: &str, entries: Vec<AproposEntry>| {
+            if entries.is_empty() {
+                eprintln!("{keyword}: nothing appropriate.");
+            }
+            for entry in &entries {
+                println!("{entry}");
+            }
+            found |= !entries.is_empty();
+        };
+
+        if mode == Mode::Whatis {
+            for name in &query_args {
+                print(name, whatis(&index, name, &sections));
+            }
+        } else {
+            let patterns = query_args
+                .iter()
+                .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    eprintln!("{e}");
+                    io::Error::new(io::ErrorKind::InvalidInput, "bad regex")
+                })?;
+            print(&query_args.join(" "), apropos(&index, &patterns, &sections));
+        }
+
+        return Ok(if found {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(EXIT_NOTHING_FOUND)
+        });
+    }
 
     // ── Single query from CLI args ──────────────────────────────────────────
     if !query_args.is_empty() {
-        let query = query_args.join(" ");
-        search_and_print(&query, &index, DEFAULT_TOP_K);
-        return Ok(());
+        let report = search_report(&query_args.join(" "), &index, &options, None);
+        print_report(&report, format, !quiet)?;
+        return Ok(ExitCode::SUCCESS);
     }
 
     // ── Interactive REPL ────────────────────────────────────────────────────
-    println!("Type a query and press Enter. Ctrl-D / empty line to exit.");
-    let stdin = io::stdin();
-    loop {
-        print!("> ");
-        io::stdout().flush()?;
+    if !quiet {
+        println!("Type a query and press Enter, :help for commands. Ctrl-D to exit.");
+    }
+    let mut repl = Repl {
+        index: &index,
+        speller: SpellChecker::build(&index),
+        options,
+        format,
+        verbose: !quiet,
+        query: None,
+        shown: Vec::new(),
+    };
+    repl.run()?;
 
-        let mut line = String::new();
-        if stdin.lock().read_line(&mut line)? == 0 || line.trim().is_empty() {
-            break;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `search shell-init <shell>`: prints the snippet for `shell`, pointing at
+/// this binary and the absolute index path.
+fn shell_init(args: &[String]) -> io::Result<ExitCode> {
+    let mut index_path = PathBuf::from(FINAL_INDEX_PATH);
+    let mut shell = None;
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().map(PathBuf::from).ok_or_else(usage)?,
+            _ => {
+                shell = Some(arg.parse::<Shell>().map_err(|e| {
+                    eprintln!("{e}");
+                    usage()
+                })?)
+            }
         }
-        search_and_print(line.trim(), &index, DEFAULT_TOP_K);
     }
+    let shell = shell.ok_or_else(usage)?;
+
+    let search = std::env::current_exe()?;
+    let index_path = std::path::absolute(&index_path)?;
+    if !index_path.exists() {
+        eprintln!(
+            "warning: no index at {}; run `cargo run --bin index` first",
+            index_path.display()
+        );
+    }
+    print!("{}", init_script(shell, &search, &index_path));
+    Ok(ExitCode::SUCCESS)
+}
+
+/// What the command-not-found hook prints, on stderr like the shell's own message.
+fn print_not_found(index: &MmapIndex, typed: &str) {
+    let suggestions = closest_commands(index, typed);
+    match suggestions.first() {
+        Some(page) if page.name.eq_ignore_ascii_case(typed) => {
+            eprintln!(
+                "{typed}: command not found, but {}({}) documents it; is it installed?",
+                page.name, page.section
+            );
+        }
+        Some(_) => {
+            eprintln!("{typed}: command not found. Did you mean:");
+            for entry in &suggestions {
+                eprintln!("  {entry}");
+            }
+        }
+        None => eprintln!("{typed}: command not found"),
+    }
+}
+
+/// Prints to stdout; a reader that stops early (`| head`) is not an error.
+fn print_report(report: &SearchReport, format: OutputFormat, verbose: bool) -> io::Result<()> {
+    match write_report(&mut io::stdout().lock(), report, format, verbose) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+const REPL_HELP: &str = "\
+  <query>            search
+  :more              next page of results
+  :open N            read result N in a pager
+  :explain N         how result N was scored
+  :section [S]       only show sections S (e.g. 1 or 1:8); no argument clears it
+  :source [NAMES]    only show pages from these sources (e.g. coreutils); no argument clears it
+  :set [KEY VALUE]   show settings, or set top-k <n> / format <text|plain|tsv|json|jsonl>
+  :help              this list
+  :quit              exit (or Ctrl-D)";
+
+struct Repl<'a> {
+    index: &'a MmapIndex,
+    /// Built once for the session rather than for every misspelt query.
+    speller: SpellChecker,
+    options: ReportOptions,
+    format: OutputFormat,
+    verbose: bool,
+    /// The last query and the doc ids shown for it so far, in rank order.
+    query: Option<String>,
+    shown: Vec<u32>,
+}
+
+impl Repl<'_> {
+    fn run(&mut self) -> io::Result<()> {
+        let config = Config::builder()
+            .max_history_size(REPL_HISTORY_SIZE)
+            .map_err(io::Error::other)?
+            .auto_add_history(true)
+            .build();
+        let mut editor = DefaultEditor::with_config(config).map_err(io::Error::other)?;
+        let history = history_path();
+        if let Some(path) = &history {
+            // A missing history file just means a first session
+            let _ = editor.load_history(path);
+        }
+
+        loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(io::Error::other(e)),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.strip_prefix(':') {
+                Some(command) => {
+                    if !self.command(command)? {
+                        break;
+                    }
+                }
+                None => self.search(line.to_string())?,
+            }
+        }
+
+        if let Some(path) = &history {
+            if let Err(e) = editor.save_history(path) {
+                eprintln!("Could not save history to {}: {e}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a `:command`; returns false to leave the REPL.
+    fn command(&mut self, command: &str) -> io::Result<bool> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let args: Vec<&str> = words.collect();
+
+        match (name, args.as_slice()) {
+            ("q" | "quit" | "exit", _) => return Ok(false),
+            ("h" | "help", _) => println!("{REPL_HELP}"),
+            ("more" | "m", _) => self.more()?,
+            ("open" | "o", [n]) => {
+                if let Some(doc_id) = self.result(n) {
+                    self.open(doc_id)?;
+                }
+            }
+            ("explain" | "e", [n]) => {
+                if let Some(doc_id) = self.result(n) {
+                    self.explain(doc_id);
+                }
+            }
+            ("section" | "s", []) => {
+                self.options.sections.clear();
+                self.rerun()?;
+            }
+            ("section" | "s", [list]) => {
+                self.options.sections = list
+                    .split([':', ','])
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                self.rerun()?;
+            }
+            ("source", []) => {
+                self.options.sources.clear();
+                self.rerun()?;
+            }
+            ("source", [list]) => {
+                self.options.sources = list
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                self.rerun()?;
+            }
+            ("set", []) => {
+                let sections = if self.options.sections.is_empty() {
+                    "all".to_string()
+                } else {
+                    self.options.sections.join(":")
+                };
+                println!("  top-k    {}", self.options.top_k);
+                println!("  format   {:?}", self.format);
+                let sources = if self.options.sources.is_empty() {
+                    "all".to_string()
+                } else {
+                    self.options.sources.join(",")
+                };
+                println!("  section  {sections}");
+                println!("  source   {sources}");
+            }
+            ("set", ["top-k" | "k", n]) => match n.parse() {
+                Ok(k) if k > 0 => self.options.top_k = k,
+                _ => println!("  top-k must be a positive number"),
+            },
+            ("set", ["format", f]) => match f.parse() {
+                Ok(format) => self.format = format,
+                Err(e) => println!("  {e}"),
+            },
+            _ => println!("  Unknown command `:{command}`; try :help"),
+        }
+        Ok(true)
+    }
+
+    fn search(&mut self, query: String) -> io::Result<()> {
+        self.options.offset = 0;
+        self.shown.clear();
+        self.query = Some(query);
+        self.show()
+    }
+
+    /// Repeats the last query from the top, e.g. after a filter change.
+    fn rerun(&mut self) -> io::Result<()> {
+        match self.query.take() {
+            Some(query) => self.search(query),
+            None => Ok(()),
+        }
+    }
+
+    fn more(&mut self) -> io::Result<()> {
+        if self.query.is_none() {
+            println!("  No query yet.");
+            return Ok(());
+        }
+        self.options.offset = self.shown.len();
+        self.show()
+    }
+
+    fn show(&mut self) -> io::Result<()> {
+        let Some(query) = &self.query else {
+            return Ok(());
+        };
+        let report = search_report(query, self.index, &self.options, Some(&self.speller));
+        if report.results.is_empty() && self.options.offset > 0 {
+            println!("  No more results.");
+            return Ok(());
+        }
+        self.shown.extend(report.results.iter().map(|r| r.doc_id));
+        print_report(&report, self.format, self.verbose)
+    }
+
+    /// Doc id of shown result number `n` (1-based).
+    fn result(&self, n: &str) -> Option<u32> {
+        let doc_id = n
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.shown.get(i).copied());
+        if doc_id.is_none() {
+            println!("  No result `{n}`; pick one of 1..={}", self.shown.len());
+        }
+        doc_id
+    }
+
+    /// Shows the page with `man`, or the indexed text in `$PAGER` if that fails.
+    fn open(&self, doc_id: u32) -> io::Result<()> {
+        let fname = &self.index.doc_map[doc_id as usize];
+        if let Some((name, section)) = split_page(fname).filter(|_| is_man_page(fname)) {
+            let status = Command::new("man").arg(section).arg(name).status();
+            if status.is_ok_and(|s| s.success()) {
+                return Ok(());
+            }
+        }
+
+        let text = self.index.doc_text(doc_id);
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let mut parts = pager.split_whitespace();
+        let spawned = parts.next().map(|program| {
+            Command::new(program)
+                .args(parts)
+                .stdin(Stdio::piped())
+                .spawn()
+        });
+        match spawned {
+            Some(Ok(mut child)) => {
+                if let Some(mut stdin) = child.stdin.take() {
+                    // The pager may quit before reading everything
+                    let _ = stdin.write_all(text.as_bytes());
+                }
+                child.wait()?;
+            }
+            _ => println!("{text}"),
+        }
+        Ok(())
+    }
+
+    fn explain(&self, doc_id: u32) {
+        let Some(query) = &self.query else {
+            return;
+        };
+        let Some(ex) = explain(
+            query,
+            self.index,
+            &self.options.sources,
+            &self.options.sections,
+            doc_id,
+        ) else {
+            println!(
+                "  {} is no longer among the results",
+                self.index.doc_map[doc_id as usize]
+            );
+            return;
+        };
+        let profile = self.index.profile();
+
+        println!("  {}  score {:.3}", ex.fname, ex.score);
+        println!(
+            "    prior        {:>10.3}  (doc type {:.2} × authority {:.2}^{:.2})",
+            ex.prior, ex.type_factor, ex.authority, profile.authority_weight
+        );
+        for term in &ex.terms {
+            let [cmd, desc, synopsis, body] = term.fields;
+            println!(
+                "    {:<12} {:>10.3}  (idf {:.2}; exact: name {cmd:.2}, description {desc:.2}, \
+                 synopsis {synopsis:.2}, body {body:.2})",
+                format!("'{}'", term.token),
+                term.total,
+                term.idf
+            );
+        }
+        if ex.associations > 0.0 {
+            println!("    associations {:>10.3}", ex.associations);
+        }
+        println!(
+            "    coverage     {:>10.3}  ({:.0}% of query idf matched)",
+            ex.coverage_factor,
+            ex.coverage * 100.0
+        );
+        println!(
+            "    rerank       {:>10.3}  (description match {:.2}, association similarity {:.2})",
+            ex.rerank_factor, ex.semantic, ex.assoc_similarity
+        );
+        #[cfg(feature = "embeddings")]
+        println!("    (final score is a rank fusion with embedding neighbours)");
+    }
+}
 
-    Ok(())
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(REPL_HISTORY_FILE))
 }
@@ -0,0 +1,146 @@
+//! `man_search segments`
+//!
+//! Edits a segment list (see `man_search::segment`): an index made of
+//! several index files that every tool accepts wherever it takes `--index`.
+//!
+//! `merge` keeps each page's link authority from its segment rather than
+//! recomputing it, so links between segments and from deleted pages are not
+//! reflected; re-run `index` over all directories to recompute it.
+//!
+//! Usage:
+//!   cargo run --bin index -- --out vendor.idx /opt/vendor/share/man
+//!   cargo run --bin segments -- man.segs add man.idx vendor.idx
+//!   cargo run --bin segments -- man.segs list
+//!   cargo run --bin segments -- man.segs delete oldtool.1.gz
+//!   cargo run --bin segments -- man.segs merge merged.idx
+//!   cargo run --bin search -- --index man.segs "copy file"
+
+use std::io;
+use std::path::Path;
+
+use man_search::index::{is_segment_file, load_index, save_index};
+use man_search::segment::{merge, Entry, SegmentList};
+
+fn usage() -> io::Error {
+    eprintln!("Usage: segments <list> list");
+    eprintln!("       segments <list> add <segment.idx>...");
+    eprintln!("       segments <list> delete <fname>...");
+    eprintln!("       segments <list> merge <out.idx>");
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+/// The list at `path`, or an empty one if it does not exist yet.
+fn read_list(path: &str) -> io::Result<SegmentList> {
+    if Path::new(path).exists() {
+        SegmentList::read(path)
+    } else {
+        Ok(SegmentList::default())
+    }
+}
+
+/// `file` as written in a list at `list_path`: relative to the list's
+/// directory when it is inside it.
+fn relative_to_list(list_path: &str, file: &str) -> io::Result<String> {
+    let dir = std::path::absolute(list_path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let file = std::path::absolute(file)?;
+    Ok(file
+        .strip_prefix(&dir)
+        .unwrap_or(&file)
+        .to_string_lossy()
+        .into_owned())
+}
+
+fn list(path: &str) -> io::Result<()> {
+    let index = load_index(path)?;
+    println!("{:<40} {:>8} {:>8} {:>10}", "segment", "docs", "live", "terms");
+    println!("{}", "─".repeat(69));
+    for segment in index.segments() {
+        println!(
+            "{:<40} {:>8} {:>8} {:>10}",
+            segment.path,
+            segment.doc_count(),
+            segment.live_docs(),
+            segment.vocabulary()
+        );
+    }
+    println!(
+        "\n{} live of {} documents, {} terms",
+        index.doc_map.len(),
+        index.total_docs(),
+        index.inverted_dict.len()
+    );
+    Ok(())
+}
+
+fn add(path: &str, files: &[String]) -> io::Result<()> {
+    let mut segments = read_list(path)?;
+    for file in files {
+        if !is_segment_file(file)? {
+            eprintln!("'{file}' is not an index file");
+            return Err(usage());
+        }
+        let entry = Entry::Segment(relative_to_list(path, file)?);
+        if segments.entries.contains(&entry) {
+            eprintln!("'{file}' is already listed");
+            continue;
+        }
+        segments.entries.push(entry);
+    }
+    segments.write(path)?;
+    // Catch unreadable segments now rather than at the next search
+    list(path)
+}
+
+fn delete(path: &str, fnames: &[String]) -> io::Result<()> {
+    let index = load_index(path)?;
+    let mut segments = SegmentList::read(path)?;
+    for fname in fnames {
+        if index.find_doc(fname).is_none() {
+            eprintln!("No live page '{fname}'");
+            continue;
+        }
+        segments.entries.push(Entry::Delete(fname.clone()));
+    }
+    segments.write(path)
+}
+
+fn merge_into(path: &str, out: &str) -> io::Result<()> {
+    let index = load_index(path)?;
+    let (live, total) = (index.doc_map.len(), index.total_docs());
+    let merged = merge(&index)?;
+    // `out` may be one of the mapped segments
+    drop(index);
+    save_index(out, &merged)?;
+
+    let segments = SegmentList {
+        entries: vec![Entry::Segment(relative_to_list(path, out)?)],
+    };
+    segments.write(path)?;
+    println!(
+        "Merged {live} live of {total} documents into '{out}'; '{path}' now lists only it. \
+         The old segment files can be removed."
+    );
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path, command, rest @ ..] = &args[..] else {
+        return Err(usage());
+    };
+    if is_segment_file(path).unwrap_or(false) {
+        eprintln!("'{path}' is an index file; segment lists are plain text");
+        return Err(usage());
+    }
+
+    match (command.as_str(), rest) {
+        ("list", []) => list(path),
+        ("add", files) if !files.is_empty() => add(path, files),
+        ("delete", fnames) if !fnames.is_empty() => delete(path, fnames),
+        ("merge", [out]) => merge_into(path, out),
+        _ => Err(usage()),
+    }
+}
@@ -1,22 +1,35 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    extract::{ConnectInfo, Query, State},
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::process::Command;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
 
-use man_search::constants::SERVER_INDEX_PATH;
+use man_search::assets::AssetStore;
+use man_search::constants::{
+    QUERY_LOG_ENV, QUERY_LOG_PATH, RELATED_DEFAULT_LIMIT, RELATED_MAX_LIMIT, SEARCH_DEFAULT_LIMIT,
+    SEARCH_MAX_LIMIT, SERVER_INDEX_PATH, STATIC_DIR_ENV, SUGGEST_DEFAULT_LIMIT, SUGGEST_MAX_LIMIT,
+    TRUSTED_PROXIES_ENV,
+};
+use man_search::doc::is_man_page;
 use man_search::index::{load_index, MmapIndex};
-use man_search::search::{search, SearchResult};
+use man_search::overstrike;
+use man_search::querylog::{LogEvent, QueryLog};
+use man_search::related::{related, Related};
+use man_search::search::{search_report, ReportOptions, SearchResult};
+use man_search::spell::SpellChecker;
+use man_search::suggest::{Suggester, Suggestion};
+use man_search::tldr::Example;
 
 // Simple token-bucket per IP: max 30 requests per 10 seconds.
 const RATE_LIMIT_WINDOW_SECS: u64 = 10;
@@ -55,6 +68,24 @@ impl RateLimiter {
 struct AppState {
     index: MmapIndex,
     rate_limiter: RateLimiter,
+    assets: AssetStore,
+    suggester: Suggester,
+    speller: SpellChecker,
+    query_log: Option<QueryLog>,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl AppState {
+    /// Best-effort: a failing log write must never fail the request.
+    fn log(&self, headers: &HeaderMap, peer: SocketAddr, event: LogEvent) {
+        let Some(log) = &self.query_log else {
+            return;
+        };
+        let client = log.client_id(&client_addr(headers, peer, &self.trusted_proxies));
+        if let Err(e) = log.record(&client, &event) {
+            eprintln!("Failed to write {}: {e}", log.path().display());
+        }
+    }
 }
 
 type SharedState = Arc<AppState>;
@@ -62,16 +93,52 @@ type SharedState = Arc<AppState>;
 #[derive(Deserialize)]
 struct SearchQuery {
     q: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    /// Comma-separated source names; adds to any `source:` in `q`.
+    source: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct SearchResponse {
+    query: String,
+    /// Stemmed terms the query was reduced to.
+    tokens: Vec<String>,
+    /// Number of hits before paging.
+    total: usize,
+    offset: usize,
+    limit: usize,
+    took_ms: f64,
+    /// Spelling-corrected query, when one of the query words looks misspelt.
+    suggestion: Option<String>,
+    results: Vec<SearchResult>,
+}
+
+#[derive(Deserialize)]
+struct SuggestQuery {
+    prefix: String,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct RelatedQuery {
+    fname: String,
+    limit: Option<usize>,
 }
 
 #[derive(Deserialize)]
 struct ContentQuery {
     fname: String,
+    /// Query and 0-based rank the page was opened from, for the click log.
+    q: Option<String>,
+    rank: Option<usize>,
 }
 
 #[derive(Serialize)]
 struct ContentResponse {
     text: String,
+    /// Example commands from the page's tldr page, if it has one.
+    examples: Vec<Example>,
 }
 
 /// Accepts only alphanumeric characters, hyphens, underscores, and dots.
@@ -107,6 +174,23 @@ fn sanitize_cmd(fname: &str) -> Option<String> {
     }
 }
 
+/// The peer address, or when the peer is one of `trusted` proxies, the last
+/// `X-Forwarded-For` hop that is not: earlier hops are set by the client.
+fn client_addr(headers: &HeaderMap, peer: SocketAddr, trusted: &[IpAddr]) -> String {
+    if !trusted.contains(&peer.ip()) {
+        return peer.ip().to_string();
+    }
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+        .rfind(|hop| !hop.parse().is_ok_and(|ip: IpAddr| trusted.contains(&ip)))
+        .map_or_else(|| peer.ip().to_string(), str::to_string)
+}
+
 /// Clamp and sanitize search queries.
 fn sanitize_query(q: &str) -> Option<String> {
     let trimmed = q.trim();
@@ -116,80 +200,166 @@ fn sanitize_query(q: &str) -> Option<String> {
     Some(trimmed.to_string())
 }
 
-fn escape_html(c: char, buf: &mut String) {
-    match c {
-        '<' => buf.push_str("&lt;"),
-        '>' => buf.push_str("&gt;"),
-        '&' => buf.push_str("&amp;"),
-        _ => buf.push(c),
-    }
-}
+/// Serves embedded static files with ETag revalidation and content negotiation.
+async fn serve_asset(State(state): State<SharedState>, headers: HeaderMap, uri: Uri) -> Response {
+    let Some(asset) = state.assets.get(uri.path()) else {
+        return (StatusCode::NOT_FOUND, "Not found").into_response();
+    };
 
-/// Converts Unix backspace formatting (`X\x08X` = bold, `_\x08X` = underline)
-/// into `<b>` / `<u>` HTML tags.
-fn parse_man_formatting(raw: &str) -> String {
-    let mut result = String::with_capacity(raw.len() * 2);
-    let chars: Vec<char> = raw.chars().collect();
-    let mut i = 0;
-    while i < chars.len() {
-        if i + 2 < chars.len() && chars[i + 1] == '\x08' {
-            let first = chars[i];
-            let second = chars[i + 2];
-            if first == '_' {
-                result.push_str("<u>");
-                escape_html(second, &mut result);
-                result.push_str("</u>");
-            } else {
-                result.push_str("<b>");
-                escape_html(second, &mut result);
-                result.push_str("</b>");
-            }
-            i += 3;
-        } else {
-            escape_html(chars[i], &mut result);
-            i += 1;
-        }
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == asset.etag || tag.trim() == "*"));
+    if not_modified {
+        return (
+            StatusCode::NOT_MODIFIED,
+            [
+                (header::ETAG, asset.etag.clone()),
+                (header::CACHE_CONTROL, asset.cache_control().to_string()),
+            ],
+        )
+            .into_response();
     }
-    result
-}
 
-async fn serve_frontend() -> Html<String> {
-    let html = fs::read_to_string("index.html").unwrap_or_else(|_| {
-        "<h1>Error: index.html not found in the project root!</h1>".to_string()
-    });
-    Html(html)
+    let accept = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let (encoding, body) = asset.body_for(accept);
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, asset.content_type.to_string()),
+            (header::ETAG, asset.etag.clone()),
+            (header::CACHE_CONTROL, asset.cache_control().to_string()),
+            (header::VARY, "Accept-Encoding".to_string()),
+        ],
+        body.to_vec(),
+    )
+        .into_response();
+    if let Some(value) = encoding.header_value() {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_ENCODING, value.parse().unwrap());
+    }
+    response
 }
 
 async fn search_api(State(state): State<SharedState>, Query(params): Query<SearchQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     // Rate limit by a fixed key (extend to real IP if behind a proxy)
     if !state.rate_limiter.check("global") {
         return (
             StatusCode::TOO_MANY_REQUESTS,
-            Json(Vec::<SearchResult>::new()),
+            Json(SearchResponse::default()),
         )
             .into_response();
     }
 
     let q = match sanitize_query(&params.q) {
         Some(q) => q,
-        None => return (StatusCode::BAD_REQUEST, Json(Vec::<SearchResult>::new())).into_response(),
+        None => return (StatusCode::BAD_REQUEST, Json(SearchResponse::default())).into_response(),
+    };
+
+    let offset = params.offset.unwrap_or(0);
+    let limit = params
+        .limit
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_MAX_LIMIT);
+
+    let sources: Vec<String> = params
+        .source
+        .iter()
+        .flat_map(|list| list.split(','))
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let options = ReportOptions {
+        top_k: limit,
+        offset,
+        sections: Vec::new(),
+        sources,
     };
 
-    let results: Vec<SearchResult> = search(&q, &state.index).into_iter().take(15).collect();
+    // The same report as the CLI, so spelling is only checked when needed
+    let started = Instant::now();
+    let report = search_report(&q, &state.index, &options, Some(&state.speller));
+    let took_ms = started.elapsed().as_secs_f64() * 1000.0;
 
-    Json(results).into_response()
+    state.log(
+        &headers,
+        peer,
+        LogEvent::Search {
+            query: &q,
+            tokens: &report.tokens,
+            total: report.total,
+            offset,
+            results: report.results.iter().map(|r| r.fname.as_str()).collect(),
+            took_ms,
+        },
+    );
+
+    Json(SearchResponse {
+        tokens: report.tokens,
+        query: q,
+        total: report.total,
+        offset,
+        limit,
+        took_ms,
+        suggestion: report.suggestion,
+        results: report.results,
+    })
+    .into_response()
+}
+
+/// Not rate limited: the frontend calls this on every keystroke and a lookup
+/// is only a binary search over an in-memory table.
+async fn suggest_api(
+    State(state): State<SharedState>,
+    Query(params): Query<SuggestQuery>,
+) -> impl IntoResponse {
+    if params.prefix.len() > 256 {
+        return (StatusCode::BAD_REQUEST, Json(Vec::<Suggestion>::new())).into_response();
+    }
+    let limit = params
+        .limit
+        .unwrap_or(SUGGEST_DEFAULT_LIMIT)
+        .min(SUGGEST_MAX_LIMIT);
+
+    Json(state.suggester.suggest(&params.prefix, limit)).into_response()
+}
+
+/// Not rate limited: the frontend calls it alongside every `/api/content`
+/// and it only reads in-memory term vectors.
+async fn related_api(
+    State(state): State<SharedState>,
+    Query(params): Query<RelatedQuery>,
+) -> impl IntoResponse {
+    let Some(doc_id) = sanitize_fname(&params.fname).and_then(|f| state.index.find_doc(f)) else {
+        return (StatusCode::NOT_FOUND, Json(Related::default())).into_response();
+    };
+    let limit = params
+        .limit
+        .unwrap_or(RELATED_DEFAULT_LIMIT)
+        .min(RELATED_MAX_LIMIT);
+
+    Json(related(&state.index, doc_id, limit)).into_response()
 }
 
 async fn content_api(
     State(state): State<SharedState>,
     Query(params): Query<ContentQuery>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     if !state.rate_limiter.check("global") {
         return (
             StatusCode::TOO_MANY_REQUESTS,
             Json(ContentResponse {
                 text: "Rate limit exceeded.".into(),
+                examples: Vec::new(),
             }),
         )
             .into_response();
@@ -203,6 +373,7 @@ async fn content_api(
                 StatusCode::BAD_REQUEST,
                 Json(ContentResponse {
                     text: "Invalid filename.".into(),
+                    examples: Vec::new(),
                 }),
             )
                 .into_response();
@@ -216,12 +387,37 @@ async fn content_api(
                 StatusCode::BAD_REQUEST,
                 Json(ContentResponse {
                     text: "Invalid command name.".into(),
+                    examples: Vec::new(),
                 }),
             )
                 .into_response();
         }
     };
 
+    state.log(
+        &headers,
+        peer,
+        LogEvent::Open {
+            fname,
+            query: params.q.as_deref().map(str::trim).filter(|q| q.len() <= 256),
+            rank: params.rank,
+        },
+    );
+
+    let doc_id = state.index.find_doc(fname);
+    let examples = doc_id
+        .map(|doc_id| state.index.examples_for(doc_id).to_vec())
+        .unwrap_or_default();
+
+    // tldr pages and info nodes are not installed for `man`; serve their indexed text
+    if !is_man_page(fname) {
+        let text = match doc_id {
+            Some(doc_id) => overstrike::to_html(state.index.doc_text(doc_id)),
+            None => format!("Could not load page '{fname}'"),
+        };
+        return Json(ContentResponse { text, examples }).into_response();
+    }
+
     // Use Command::new instead of sh -c to avoid shell injection entirely.
     // Pass env vars and the command name as discrete arguments — no shell interpolation.
     let output = Command::new("man")
@@ -234,12 +430,12 @@ async fn content_api(
     let text = match output {
         Ok(out) if out.status.success() => {
             let raw = String::from_utf8_lossy(&out.stdout);
-            parse_man_formatting(&raw)
+            overstrike::to_html(&raw)
         }
         _ => format!("Could not load man page for '{cmd}'"),
     };
 
-    Json(ContentResponse { text }).into_response()
+    Json(ContentResponse { text, examples }).into_response()
 }
 
 #[tokio::main]
@@ -250,18 +446,72 @@ async fn main() {
         std::process::exit(1);
     });
 
+    let static_dir = std::env::var_os(STATIC_DIR_ENV).map(PathBuf::from);
+    if let Some(dir) = &static_dir {
+        println!("Serving static files from {} (override)", dir.display());
+    }
+    let assets = AssetStore::new(static_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to prepare static assets: {e}");
+        std::process::exit(1);
+    });
+
+    let suggester = Suggester::build(&index);
+    let speller = SpellChecker::build(&index);
+
+    let log_path = std::env::var(QUERY_LOG_ENV).unwrap_or_else(|_| QUERY_LOG_PATH.to_string());
+    let query_log = if log_path.is_empty() {
+        None
+    } else {
+        match QueryLog::open(&log_path) {
+            Ok(log) => {
+                println!("Logging queries to {log_path}");
+                Some(log)
+            }
+            Err(e) => {
+                eprintln!("Query logging disabled ({log_path}: {e})");
+                None
+            }
+        }
+    };
+
+    let trusted_proxies: Vec<IpAddr> = std::env::var(TRUSTED_PROXIES_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .filter_map(|addr| match addr.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                eprintln!("Ignoring {TRUSTED_PROXIES_ENV} entry `{addr}`: not an IP address");
+                None
+            }
+        })
+        .collect();
+
     let state = Arc::new(AppState {
         index,
         rate_limiter: RateLimiter::new(),
+        assets,
+        suggester,
+        speller,
+        query_log,
+        trusted_proxies,
     });
 
     let app = Router::new()
-        .route("/", get(serve_frontend))
         .route("/api/search", get(search_api))
+        .route("/api/suggest", get(suggest_api))
         .route("/api/content", get(content_api))
+        .route("/api/related", get(related_api))
+        .fallback(serve_asset)
         .with_state(state);
 
     let listener = TcpListener::bind("0.0.0.0:3000").await.unwrap();
     println!("Server running at http://0.0.0.0:3000");
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -0,0 +1,173 @@
+//! `man_search train`
+//!
+//! Fits the ranking profile (field weights, section factors, rerank weights,
+//! prefix/fuzzy penalties) to judged queries by coordinate ascent on mean
+//! nDCG@10, and writes it next to the index, where `load_index` picks it up.
+//!
+//! Usage:
+//!   cargo run --bin train -- --judgments judgments.tsv
+//!   cargo run --bin train -- --log search-log.jsonl --out ranking.json
+//!
+//! Judgments can come from `eval`-style TSV files, from the server's query
+//! log (pages opened from a query count as relevant), or both.
+
+use std::io;
+use std::path::Path;
+
+use man_search::constants::{FINAL_INDEX_PATH, RANKING_PROFILE_PATH};
+use man_search::eval::{
+    evaluate_index, judgments_from_log, load_judgments, QueryJudgments, QueryMetrics,
+};
+use man_search::index::{load_index, MmapIndex};
+use man_search::profile::RankingProfile;
+
+const DEFAULT_ROUNDS: usize = 5;
+/// Multipliers tried for each parameter per round.
+const STEPS: [f32; 6] = [0.25, 0.5, 0.8, 1.25, 2.0, 4.0];
+/// Smallest gain in the objective that counts as an improvement.
+const MIN_GAIN: f32 = 1e-4;
+/// Every this-many-th query is held out to check the fit generalises.
+const HOLDOUT_EVERY: usize = 5;
+/// Below this many queries everything is used for training.
+const HOLDOUT_MIN_QUERIES: usize = 10;
+
+fn usage() -> io::Error {
+    eprintln!(
+        "Usage: train [--index <path>] [--judgments <file.tsv>]... [--log <file.jsonl>]... \\
+         [--out <path>] [--rounds <n>]"
+    );
+    io::Error::new(io::ErrorKind::InvalidInput, "bad arguments")
+}
+
+fn objective(judgments: &[QueryJudgments], index: &MmapIndex) -> f32 {
+    QueryMetrics::mean(&evaluate_index(judgments, index, 10)).ndcg
+}
+
+/// Parameters that are penalties (a fraction of an exact match) stay in [0, 1].
+fn clamp(name: &str, value: f32) -> f32 {
+    match name {
+        "prefix_decay" | "fuzzy_penalty" => value.clamp(0.0, 1.0),
+        _ => value.max(0.0),
+    }
+}
+
+fn set_param(profile: &RankingProfile, i: usize, value: f32) -> RankingProfile {
+    let mut p = profile.clone();
+    *p.params_mut()[i].1 = value;
+    p
+}
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut index_path = FINAL_INDEX_PATH.to_string();
+    let mut out_path: Option<String> = None;
+    let mut rounds = DEFAULT_ROUNDS;
+    let mut judgments: Vec<QueryJudgments> = Vec::new();
+
+    let mut it = args.into_iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--index" => index_path = it.next().ok_or_else(usage)?,
+            "--out" => out_path = Some(it.next().ok_or_else(usage)?),
+            "--rounds" => rounds = it.next().and_then(|v| v.parse().ok()).ok_or_else(usage)?,
+            "--judgments" => judgments.extend(load_judgments(&it.next().ok_or_else(usage)?)?),
+            "--log" => judgments.extend(judgments_from_log(&it.next().ok_or_else(usage)?)?),
+            _ => return Err(usage()),
+        }
+    }
+    if judgments.is_empty() {
+        eprintln!("No judged queries; pass --judgments and/or --log");
+        return Err(usage());
+    }
+    let out_path = out_path.unwrap_or_else(|| {
+        Path::new(&index_path)
+            .with_file_name(RANKING_PROFILE_PATH)
+            .to_string_lossy()
+            .into_owned()
+    });
+
+    let (train, holdout): (Vec<_>, Vec<_>) = if judgments.len() >= HOLDOUT_MIN_QUERIES {
+        let (h, t): (Vec<_>, Vec<_>) = judgments
+            .into_iter()
+            .enumerate()
+            .partition(|(i, _)| i % HOLDOUT_EVERY == HOLDOUT_EVERY - 1);
+        (
+            t.into_iter().map(|(_, j)| j).collect(),
+            h.into_iter().map(|(_, j)| j).collect(),
+        )
+    } else {
+        (judgments, Vec::new())
+    };
+
+    eprint!("Loading index '{index_path}'… ");
+    let mut index = load_index(&index_path).map_err(|e| {
+        eprintln!("\nFailed to load index: {e}");
+        e
+    })?;
+    eprintln!("OK ({} docs)", index.doc_map.len());
+
+    let mut best = index.profile().clone();
+    let start = best.clone();
+    let mut best_score = objective(&train, &index);
+    let start_score = best_score;
+    println!(
+        "{} training queries, {} held out  |  start nDCG@10 = {best_score:.4}",
+        train.len(),
+        holdout.len()
+    );
+
+    for round in 1..=rounds {
+        let round_start = best_score;
+        for i in 0..start.params().len() {
+            let (name, current) = best.params()[i];
+            let base = if current == 0.0 { 1.0 } else { current };
+            let mut candidates: Vec<f32> = STEPS.iter().map(|m| clamp(name, base * m)).collect();
+            candidates.push(0.0);
+
+            for value in candidates {
+                if value == current {
+                    continue;
+                }
+                index.set_profile(set_param(&best, i, value));
+                let score = objective(&train, &index);
+                if score > best_score + MIN_GAIN {
+                    best_score = score;
+                    best = set_param(&best, i, value);
+                }
+            }
+        }
+        println!("round {round}: nDCG@10 = {best_score:.4}");
+        if best_score <= round_start + MIN_GAIN {
+            break;
+        }
+    }
+
+    println!("\nChanged parameters:");
+    let mut changed = 0;
+    for ((name, old), (_, new)) in start.params().into_iter().zip(best.params()) {
+        if old != new {
+            println!("  {name:<22} {old:>8.3} → {new:.3}");
+            changed += 1;
+        }
+    }
+    if changed == 0 {
+        println!("  (none)");
+    }
+
+    if !holdout.is_empty() {
+        index.set_profile(start);
+        let before = objective(&holdout, &index);
+        index.set_profile(best.clone());
+        let after = objective(&holdout, &index);
+        println!("\nHeld-out nDCG@10: {before:.4} → {after:.4}");
+    }
+
+    if best_score <= start_score + MIN_GAIN {
+        println!("\nNo improvement; '{out_path}' left untouched.");
+        return Ok(());
+    }
+    best.save(&out_path)?;
+    println!("\nWrote ranking profile to '{out_path}'");
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Explains a shell command line from the man page of its command: the
+//! page's one-line description plus the text documenting each option used.
+
+use serde::Serialize;
+
+use crate::apropos::whatis;
+use crate::constants::{OPTION_HELP_MAX_LINES, PAGE_RENDER_WIDTH};
+use crate::doc::render_formatted;
+use crate::index::MmapIndex;
+use crate::overstrike::strip;
+
+#[derive(Serialize)]
+pub struct OptionHelp {
+    /// The option as written on the command line, e.g. `-p` or `--parents`.
+    pub option: String,
+    /// What the page says about it; `None` if the page does not list it.
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CommandHelp {
+    pub command: String,
+    pub fname: String,
+    pub name_desc: String,
+    pub options: Vec<OptionHelp>,
+}
+
+/// True for `FOO=bar` environment assignments before a command.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Option names documented by the start of an option line, e.g.
+/// `-c, --color[=WHEN]` gives `-c` and `--color`, plus the text after them.
+fn option_spec(line: &str) -> (Vec<&str>, &str) {
+    // Rendered pages separate the option list from its text by 2+ spaces
+    let (spec, rest) = match line.find("  ") {
+        Some(gap) => (&line[..gap], line[gap..].trim()),
+        None => (line, ""),
+    };
+    let mut names = Vec::new();
+    let mut consumed = 0;
+    for word in spec.split_whitespace() {
+        if !word.starts_with('-') {
+            break;
+        }
+        let name = word
+            .trim_end_matches(',')
+            .split(['=', '['])
+            .next()
+            .unwrap_or("");
+        names.push(name);
+        consumed = spec.find(word).map_or(consumed, |p| p + word.len());
+    }
+    // Without a wide gap, whatever follows the option words is the text
+    let rest = if rest.is_empty() {
+        spec[consumed..].trim()
+    } else {
+        rest
+    };
+    (names, rest)
+}
+
+fn is_heading(line: &str) -> bool {
+    !line.starts_with(char::is_whitespace)
+        && line.chars().any(char::is_alphabetic)
+        && line.chars().all(|c| !c.is_lowercase())
+}
+
+/// The paragraph documenting `option` in page `text`.
+fn option_help(text: &str, option: &str) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('-') {
+            continue;
+        }
+        let (names, rest) = option_spec(trimmed);
+        if !names.contains(&option) {
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        let mut description: Vec<&str> = Vec::new();
+        if !rest.is_empty() {
+            description.push(rest);
+        }
+        for next in &lines[i + 1..] {
+            let next_trimmed = next.trim_start();
+            if next_trimmed.is_empty() {
+                if description.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            let next_indent = next.len() - next_trimmed.len();
+            if is_heading(next)
+                || next_indent < indent
+                || (next_indent == indent && next_trimmed.starts_with('-'))
+                || description.len() >= OPTION_HELP_MAX_LINES
+            {
+                break;
+            }
+            description.push(next_trimmed);
+        }
+        return Some(description.join(" "));
+    }
+    None
+}
+
+/// Looks `option` up, splitting bundled short flags (`-xzf`) when the
+/// page does not document the bundle itself (as `find -name` would be).
+fn explain_option(text: &str, option: &str, out: &mut Vec<OptionHelp>) {
+    let name = option.split('=').next().unwrap_or(option);
+    let description = option_help(text, name);
+    let bundled = !name.starts_with("--") && name.len() > 2;
+    if description.is_some() || !bundled {
+        out.push(OptionHelp {
+            option: option.to_string(),
+            description,
+        });
+        return;
+    }
+    for flag in name[1..].chars() {
+        let flag = format!("-{flag}");
+        out.push(OptionHelp {
+            description: option_help(text, &flag),
+            option: flag,
+        });
+    }
+}
+
+/// Explains the first simple command of `line` (up to `|`, `;`, `&&`, …).
+/// `git commit` style subcommands use the `git-commit` page when it exists.
+/// `None` if the command has no page in the index.
+pub fn explain_command(index: &MmapIndex, line: &str) -> Option<CommandHelp> {
+    let words: Vec<&str> = line
+        .split_whitespace()
+        .skip_while(|w| is_assignment(w))
+        .take_while(|w| !matches!(*w, "|" | "||" | "&&" | ";" | "&"))
+        .collect();
+    let (&first, mut args) = words.split_first()?;
+    let mut command = first.rsplit('/').next().unwrap_or(first).to_string();
+
+    if let Some(&sub) = args.first().filter(|s| !s.starts_with('-')) {
+        let paged = format!("{command}-{sub}");
+        if !whatis(index, &paged, &[]).is_empty() {
+            command = paged;
+            args = &args[1..];
+        }
+    }
+    let page = whatis(index, &command, &[]).into_iter().next()?;
+
+    let text = render_formatted(&page.name, &page.section, PAGE_RENDER_WIDTH)
+        .map(|raw| strip(&raw))
+        .unwrap_or_else(|| index.doc_text(page.doc_id).to_string());
+
+    let mut options = Vec::new();
+    for arg in args {
+        if *arg == "--" {
+            break;
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            explain_option(&text, arg, &mut options);
+        }
+    }
+
+    Some(CommandHelp {
+        command,
+        fname: index.doc_map[page.doc_id as usize].clone(),
+        name_desc: page.description,
+        options,
+    })
+}
@@ -2,7 +2,7 @@
 pub const BM25_K1: f32 = 1.5;
 pub const BM25_B: f32 = 0.75;
 
-// Field weights
+// Field weights (defaults for `RankingProfile`)
 pub const WEIGHT_CMD_NAME: f32 = 30.0;
 pub const WEIGHT_NAME_DESC: f32 = 12.0;
 pub const WEIGHT_SYNOPSIS: f32 = 2.5;
@@ -12,6 +12,44 @@ pub const WEIGHT_BODY: f32 = 1.0;
 pub const SEMANTIC_RERANK_N: usize = 50;
 pub const SEMANTIC_WEIGHT: f32 = 15.0;
 
+// Query expansion
+/// Score multiplier for postings reached through a synonym instead of the query term itself.
+pub const SYNONYM_WEIGHT: f32 = 0.5;
+
+// Term associations (mined at index time)
+/// Pairs must co-occur in at least this many documents' NAME/synopsis fields.
+pub const ASSOC_MIN_COOCCUR: u32 = 3;
+/// Only the first this-many distinct terms of a document are paired up.
+pub const ASSOC_MAX_DOC_TERMS: usize = 48;
+/// Minimum normalised PMI for a pair to count as associated.
+pub const ASSOC_MIN_NPMI: f32 = 0.25;
+/// Related terms kept per term.
+pub const ASSOC_TOP_K: usize = 8;
+/// Weight of associated terms relative to the query term (multiplied by NPMI).
+pub const ASSOC_EXPANSION_WEIGHT: f32 = 0.3;
+/// Weight of the association-expanded cosine in the semantic rerank.
+pub const ASSOC_RERANK_WEIGHT: f32 = 4.0;
+
+// Link authority (PageRank over `name(section)` references)
+pub const PAGERANK_DAMPING: f32 = 0.85;
+pub const PAGERANK_ITERATIONS: usize = 40;
+/// Documents are boosted by `authority ^ AUTHORITY_WEIGHT` (authority averages 1.0).
+pub const AUTHORITY_WEIGHT: f32 = 0.3;
+
+// Related pages
+/// Terms kept per document for content similarity.
+pub const RELATED_TERMS_PER_DOC: usize = 32;
+/// Pages less similar than this (cosine) are never suggested.
+pub const RELATED_MIN_SIMILARITY: f32 = 0.1;
+pub const RELATED_DEFAULT_LIMIT: usize = 8;
+pub const RELATED_MAX_LIMIT: usize = 25;
+
+// Dense vectors (`embeddings` feature)
+/// Number of nearest neighbours fused with the BM25 ranking.
+pub const VECTOR_TOP_K: usize = 50;
+/// Rank offset in reciprocal rank fusion; larger values flatten the head of each list.
+pub const RRF_K: f32 = 60.0;
+
 // Fuzzy / prefix search
 /// Minimum token length before prefix expansion is attempted.
 pub const PREFIX_MIN_LEN: usize = 4;
@@ -19,14 +57,118 @@ pub const PREFIX_MIN_LEN: usize = 4;
 pub const PREFIX_MIN_IDF: f32 = 1.0;
 /// Minimum token length before fuzzy (edit-distance) matching is attempted.
 pub const FUZZY_MIN_LEN: usize = 4;
+/// Prefix matches score `PREFIX_DECAY ^ (extra chars + 1)` of an exact match.
+pub const PREFIX_DECAY: f32 = 0.6;
+/// Score multiplier for fuzzy (edit distance 1) matches.
+pub const FUZZY_PENALTY: f32 = 0.5;
+
+// Autocomplete
+/// Dictionary terms must appear in at least this many documents to be suggested.
+pub const SUGGEST_MIN_DF: u32 = 2;
+/// Term suggestions are scaled by this so command names rank first.
+pub const SUGGEST_TERM_WEIGHT: f32 = 0.5;
+pub const SUGGEST_DEFAULT_LIMIT: usize = 8;
+pub const SUGGEST_MAX_LIMIT: usize = 25;
+
+// Snippets
+/// Page text stored in the index for snippets is cut off after this many bytes.
+pub const DOC_TEXT_MAX_BYTES: usize = 24 * 1024;
+/// Length of a result snippet, in words.
+pub const SNIPPET_WORDS: usize = 28;
+
+// Spelling correction
+/// Words shorter than this are never corrected.
+pub const SPELL_MIN_LEN: usize = 3;
+/// Words whose stem appears in at least this many documents are considered correct.
+pub const SPELL_KNOWN_DF: u32 = 3;
+/// Score lost per edit when ranking candidates by `ln(1 + df)`.
+pub const SPELL_DISTANCE_PENALTY: f32 = 2.0;
+
+// Web API paging
+pub const SEARCH_DEFAULT_LIMIT: usize = 15;
+pub const SEARCH_MAX_LIMIT: usize = 100;
 
 // Index file paths
 pub const TEMP_INDEX_PATH: &str = "temp_index.bin";
 pub const FINAL_INDEX_PATH: &str = "temp-man.idx";
 pub const SERVER_INDEX_PATH: &str = "man.idx";
+/// Word-vector model (word2vec/GloVe text format) used by `index` with the `embeddings` feature.
+pub const EMBEDDING_MODEL_PATH: &str = "embeddings.vec";
+/// User-extendable synonym table, read by `load_index` from the directory of
+/// the index when present.
+pub const SYNONYMS_PATH: &str = "synonyms.txt";
+/// Ranking parameters written by `train`, read by `load_index` from the
+/// directory of the index when present.
+pub const RANKING_PROFILE_PATH: &str = "ranking.json";
+
+// Index format
+/// Every index file starts with this magic followed by a u32 format version.
+pub const INDEX_MAGIC: &[u8; 4] = b"MIDX";
+pub const INDEX_VERSION: u32 = 7;
+
+// Interactive search
+/// REPL history file, relative to `$HOME`.
+pub const REPL_HISTORY_FILE: &str = ".man_search_history";
+pub const REPL_HISTORY_SIZE: usize = 1000;
+/// Results listed by the full-screen UI.
+pub const TUI_MAX_RESULTS: usize = 100;
+
+// Local daemon
+/// Socket file name, placed in `$XDG_RUNTIME_DIR`, or else in a private
+/// `DAEMON_SOCKET_DIR-$USER` directory of the temp dir.
+pub const DAEMON_SOCKET_NAME: &str = "man_search.sock";
+pub const DAEMON_SOCKET_DIR: &str = "man_search";
+/// Width pages are rendered at outside a terminal.
+pub const PAGE_RENDER_WIDTH: u16 = 80;
+/// Most lines of an option's description that `explain-cmd` returns.
+pub const OPTION_HELP_MAX_LINES: usize = 6;
+
+// Example pages (tldr-pages)
+/// tldr pages are indexed as `<command>.tldr`, as if in a section of their own.
+pub const TLDR_SECTION: &str = "tldr";
+/// Platform directories of a tldr-pages checkout that are crawled; the others
+/// and the translations (`pages.de`, …) are skipped.
+pub const TLDR_PLATFORMS: &[&str] = &["common", "linux"];
+/// Examples printed under each result in the text output.
+pub const RESULT_EXAMPLES: usize = 3;
+
+// Info manuals
+/// Info nodes are indexed as `<manual>-<node>.info`.
+pub const INFO_SECTION: &str = "info";
+/// Longest info node file name; longer node names are cut short. The server
+/// accepts no longer file names.
+pub const INFO_FNAME_MAX_BYTES: usize = 64;
+
+// Shell integration
+/// Results the key-binding widget offers to `fzf`.
+pub const SHELL_WIDGET_RESULTS: usize = 30;
+/// Commands the command-not-found hook suggests.
+pub const NOT_FOUND_MAX_SUGGESTIONS: usize = 5;
+
+// Web frontend
+/// When set, the server reads static files from this directory instead of the embedded copies.
+pub const STATIC_DIR_ENV: &str = "MAN_SEARCH_STATIC_DIR";
+/// Comma-separated addresses of reverse proxies whose `X-Forwarded-For` is
+/// believed; from anyone else the peer address identifies the client.
+pub const TRUSTED_PROXIES_ENV: &str = "MAN_SEARCH_TRUSTED_PROXIES";
+
+// Query/click log
+pub const QUERY_LOG_PATH: &str = "search-log.jsonl";
+/// Overrides `QUERY_LOG_PATH`; set it to an empty string to disable logging.
+pub const QUERY_LOG_ENV: &str = "MAN_SEARCH_LOG";
+/// The log is rotated to `<path>.1` once it would grow past this size.
+pub const QUERY_LOG_MAX_BYTES: u64 = 16 * 1024 * 1024;
+/// Rotated files kept (`<path>.1` … `<path>.N`, at least 1); older ones are overwritten.
+pub const QUERY_LOG_KEEP: usize = 4;
 
 // Source directories
-pub const SOURCE_DIRS: [&str; 2] = ["man-pages-6.9.1/man", "pure_coreutils_man/"];
+/// (source name, directory); every page is tagged with the name of the entry it was crawled from.
+pub const SOURCE_DIRS: [(&str, &str); 4] = [
+    ("man-pages", "man-pages-6.9.1/man"),
+    ("coreutils", "pure_coreutils_man/"),
+    ("tldr", "tldr/pages"),
+    ("info", "/usr/share/info"),
+];
 
 // VIP commands (boosted in ranking)
 pub const VIP_COMMANDS: &[&str] = &[
@@ -38,3 +180,39 @@ pub const VIP_COMMANDS: &[&str] = &[
     "stat", "touch", "tail", "head", "sort", "uniq", "wc", "read", "gzip",
     "bzip2", "unzip", "zip", "chgrp", "date", "cal", "whoami",
 ];
+
+// Built-in query intents; extended by `SYNONYMS_PATH`
+pub const DEFAULT_SYNONYMS: &[(&str, &[&str])] = &[
+    ("delete", &["remove", "unlink", "erase"]),
+    ("erase", &["remove", "delete"]),
+    ("remove", &["delete", "unlink"]),
+    ("show", &["display", "print", "list", "report"]),
+    ("display", &["show", "print"]),
+    ("list", &["display", "show"]),
+    ("folder", &["directory"]),
+    ("dir", &["directory"]),
+    ("make", &["create"]),
+    ("create", &["make"]),
+    ("duplicate", &["copy"]),
+    ("rename", &["move"]),
+    ("move", &["rename"]),
+    ("find", &["search", "locate"]),
+    ("search", &["find", "locate"]),
+    ("edit", &["modify", "change"]),
+    ("modify", &["change"]),
+    ("kill", &["terminate", "signal"]),
+    ("stop", &["kill", "terminate"]),
+    ("terminate", &["kill"]),
+    ("compress", &["archive", "zip"]),
+    ("extract", &["decompress", "unpack", "expand"]),
+    ("unzip", &["decompress", "extract"]),
+    ("size", &["space", "usage"]),
+    ("space", &["size", "usage"]),
+    ("disk", &["filesystem"]),
+    ("permission", &["mode"]),
+    ("download", &["fetch", "transfer", "retrieve"]),
+    ("run", &["execute"]),
+    ("execute", &["run"]),
+    ("password", &["passwd"]),
+    ("time", &["date", "clock"]),
+];
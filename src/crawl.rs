@@ -4,8 +4,10 @@ use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
 
 use crate::doc::parse_doc;
-use crate::io_util::{write_str, write_tf_map, write_u32};
+use crate::info::{is_info_file, is_info_page, parse_info_docs};
+use crate::io_util::{write_str, write_str_list, write_tf_map, write_u32};
 use crate::text::make_stemmer;
+use crate::tldr::{is_skipped_dir, is_tldr_file, parse_tldr_doc, write_examples};
 
 pub struct CrawlStats {
     pub total_docs: u32,
@@ -15,10 +17,11 @@ pub struct CrawlStats {
     pub avg_body_len: f32,
 }
 
-/// Walk `source_dirs`, parse every man-page found, and stream raw
-/// per-document data to `out_path`.  Returns aggregate statistics
-/// needed for BM25 normalisation in Pass 2.
-pub fn crawl(source_dirs: &[&str], out_path: &str) -> io::Result<CrawlStats> {
+/// Walk the directory of every `(name, dir)` source, parse every man-page,
+/// tldr page and info node found, and stream raw per-document data, tagged with the source name, to
+/// `out_path`.  Returns aggregate statistics needed for BM25 normalisation
+/// in Pass 2.
+pub fn crawl(sources: &[(&str, &str)], out_path: &str) -> io::Result<CrawlStats> {
     let stemmer = make_stemmer();
     let file = File::create(out_path)?;
     let mut writer = BufWriter::new(file);
@@ -28,11 +31,16 @@ pub fn crawl(source_dirs: &[&str], out_path: &str) -> io::Result<CrawlStats> {
     let mut sum_desc = 0u64;
     let mut sum_synopsis = 0u64;
     let mut sum_body = 0u64;
+    // Info nodes indexed so far; a manual installed twice is indexed once
+    let mut info_fnames: HashSet<String> = HashSet::new();
 
     // Iterative DFS over all source directories
-    let mut dirs: Vec<PathBuf> = source_dirs.iter().map(PathBuf::from).collect();
+    let mut dirs: Vec<(PathBuf, &str)> = sources
+        .iter()
+        .map(|&(name, dir)| (PathBuf::from(dir), name))
+        .collect();
 
-    while let Some(dir) = dirs.pop() {
+    while let Some((dir, source)) = dirs.pop() {
         let Ok(entries) = fs::read_dir(&dir) else {
             continue;
         };
@@ -40,7 +48,9 @@ pub fn crawl(source_dirs: &[&str], out_path: &str) -> io::Result<CrawlStats> {
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_dir() {
-                dirs.push(path);
+                if !is_skipped_dir(&path) {
+                    dirs.push((path, source));
+                }
                 continue;
             }
             if !path.is_file() {
@@ -53,44 +63,65 @@ pub fn crawl(source_dirs: &[&str], out_path: &str) -> io::Result<CrawlStats> {
                 .to_string_lossy()
                 .into_owned();
 
-            let Some(doc) = parse_doc(&path, &fname, &stemmer) else {
-                continue;
+            let docs = if is_info_file(&path) {
+                parse_info_docs(&path, &stemmer)
+            } else if is_tldr_file(&path) {
+                parse_tldr_doc(&path, &stemmer).into_iter().collect()
+            } else {
+                parse_doc(&path, &fname, &stemmer).into_iter().collect()
             };
-
-            // Update global document-frequency counts
-            let mut seen: HashSet<&String> = HashSet::new();
-            for w in doc
-                .name_desc_tf
-                .keys()
-                .chain(doc.synopsis_tf.keys())
-                .chain(doc.body_tf.keys())
-            {
-                if seen.insert(w) {
-                    *global_df.entry(w.clone()).or_insert(0) += 1;
+            let mut duplicates = 0;
+            for doc in docs {
+                if is_info_page(&doc.fname) && !info_fnames.insert(doc.fname.clone()) {
+                    duplicates += 1;
+                    continue;
+                }
+                // Update global document-frequency counts
+                let mut seen: HashSet<&String> = HashSet::new();
+                for w in doc
+                    .name_desc_tf
+                    .keys()
+                    .chain(doc.synopsis_tf.keys())
+                    .chain(doc.body_tf.keys())
+                {
+                    if seen.insert(w) {
+                        *global_df.entry(w.clone()).or_insert(0) += 1;
+                    }
+                }
+                if !doc.cmd_name.is_empty() && seen.insert(&doc.cmd_name) {
+                    *global_df.entry(doc.cmd_name.clone()).or_insert(0) += 1;
                 }
-            }
-            if !doc.cmd_name.is_empty() && seen.insert(&doc.cmd_name) {
-                *global_df.entry(doc.cmd_name.clone()).or_insert(0) += 1;
-            }
 
-            sum_desc += doc.name_desc_len as u64;
-            sum_synopsis += doc.synopsis_len as u64;
-            sum_body += doc.body_len as u64;
+                sum_desc += doc.name_desc_len as u64;
+                sum_synopsis += doc.synopsis_len as u64;
+                sum_body += doc.body_len as u64;
 
-            // Serialise document to temp file
-            write_str(&mut writer, &doc.fname)?;
-            write_str(&mut writer, &doc.cmd_name)?;
-            write_u32(&mut writer, doc.name_desc_len)?;
-            write_u32(&mut writer, doc.synopsis_len)?;
-            write_u32(&mut writer, doc.body_len)?;
-            write_tf_map(&mut writer, &doc.name_desc_tf)?;
-            write_tf_map(&mut writer, &doc.synopsis_tf)?;
-            write_tf_map(&mut writer, &doc.body_tf)?;
-            write_str(&mut writer, &doc.name_desc_raw)?;
+                // Serialise document to temp file
+                write_str(&mut writer, &doc.fname)?;
+                write_str(&mut writer, source)?;
+                write_str(&mut writer, &doc.cmd_name)?;
+                write_u32(&mut writer, doc.name_desc_len)?;
+                write_u32(&mut writer, doc.synopsis_len)?;
+                write_u32(&mut writer, doc.body_len)?;
+                write_tf_map(&mut writer, &doc.name_desc_tf)?;
+                write_tf_map(&mut writer, &doc.synopsis_tf)?;
+                write_tf_map(&mut writer, &doc.body_tf)?;
+                write_str(&mut writer, &doc.name_desc_raw)?;
+                write_str(&mut writer, &doc.text)?;
+                write_str_list(&mut writer, &doc.refs.see_also)?;
+                write_str_list(&mut writer, &doc.refs.mentions)?;
+                write_examples(&mut writer, &doc.examples)?;
 
-            total_docs += 1;
-            print!("\rIndexed: {total_docs}");
-            io::stdout().flush().unwrap();
+                total_docs += 1;
+                print!("\rIndexed: {total_docs}");
+                io::stdout().flush().unwrap();
+            }
+            if duplicates > 0 {
+                eprintln!(
+                    "\nSkipped {duplicates} nodes of {}: already indexed from another copy",
+                    path.display()
+                );
+            }
         }
     }
 
@@ -4,8 +4,12 @@ use std::io;
 use std::path::Path;
 use std::process::Command;
 
-use crate::constants::VIP_COMMANDS;
+use crate::constants::{DOC_TEXT_MAX_BYTES, INFO_SECTION, TLDR_SECTION, VIP_COMMANDS};
+use crate::graph::{extract_refs, PageRefs};
+use crate::info::is_info_page;
+use crate::profile::RankingProfile;
 use crate::text::tokenize;
+use crate::tldr::{is_tldr_page, Example};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum Section {
@@ -72,32 +76,36 @@ pub struct DocFields {
     pub synopsis_len: u32,
     pub body_tf: HashMap<String, u32>,
     pub body_len: u32,
+    /// Whitespace-normalised page text kept for result snippets.
+    pub text: String,
+    /// `name(section)` references to other pages.
+    pub refs: PageRefs,
+    /// Example commands; only tldr pages have them.
+    pub examples: Vec<Example>,
 }
 
 /// Document-type score multiplier derived from the filename / section number.
-pub fn doc_type_multiplier(fname: &str) -> f32 {
+pub fn doc_type_multiplier(fname: &str, profile: &RankingProfile) -> f32 {
     // Skip index / heading files
     if fname.ends_with("const") || fname.ends_with("type") || fname.ends_with("head") {
         return 0.1;
     }
 
-    let section_mult = match fname
-        .rsplit('.')
-        .next()
-        .and_then(|s| s.chars().next())
-        .and_then(|c| c.to_digit(10))
-    {
-        Some(1) => 4.0,           // User commands
-        Some(8) => 2.5,           // Sysadmin commands
-        Some(5) => 1.2,           // Config files
-        Some(2) | Some(3) => 0.8, // Dev libs / syscalls
-        Some(4) | Some(6) | Some(7) => 0.6,
-        _ => 0.8,
+    let section = fname.rsplit('.').next().unwrap_or("");
+    let section_mult = match section.chars().next().and_then(|c| c.to_digit(10)) {
+        Some(1) => profile.section_user,           // User commands
+        Some(8) => profile.section_admin,          // Sysadmin commands
+        Some(5) => profile.section_config,         // Config files
+        Some(2) | Some(3) => profile.section_dev,  // Dev libs / syscalls
+        Some(4) | Some(6) | Some(7) => profile.section_misc,
+        _ if section == TLDR_SECTION => profile.section_examples, // Example pages
+        _ if section == INFO_SECTION => profile.section_info,     // Info nodes
+        _ => profile.section_other,
     };
 
     let base = fname.split('.').next().unwrap_or("").to_lowercase();
     let vip_mult = if VIP_COMMANDS.contains(&base.as_str()) {
-        5.0
+        profile.vip_boost
     } else {
         1.0
     };
@@ -112,14 +120,68 @@ fn render_man_page(path: &Path) -> io::Result<String> {
         .output()?;
 
     if !output.status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("man command failed for {:?}", path),
-        ));
+        return Err(io::Error::other(format!(
+            "man command failed for {:?}",
+            path
+        )));
     }
     Ok(String::from_utf8_lossy(&output.stdout).into_owned())
 }
 
+/// False for tldr pages and info nodes, which `man` cannot show; their
+/// indexed text stands in for the rendered page.
+pub fn is_man_page(fname: &str) -> bool {
+    !is_tldr_page(fname) && !is_info_page(fname)
+}
+
+/// Formats page `name` of `section` with `man` for `width` columns, keeping
+/// the overstrike bold/underline; `None` if `man` fails.
+pub fn render_formatted(name: &str, section: &str, width: u16) -> Option<String> {
+    let output = Command::new("man")
+        .env("MANWIDTH", width.to_string())
+        .env("MAN_KEEP_FORMATTING", "1")
+        .env("GROFF_NO_SGR", "1")
+        .env("PAGER", "cat")
+        .arg(section)
+        .arg(name)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Trim every line, collapse runs of whitespace and drop blank lines,
+/// keeping at most `DOC_TEXT_MAX_BYTES` of the result.
+pub(crate) fn normalise_text(content: &str) -> String {
+    let mut text = String::with_capacity(content.len().min(DOC_TEXT_MAX_BYTES));
+    for line in content.lines() {
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        for (i, w) in words.enumerate() {
+            if i > 0 {
+                text.push(' ');
+            }
+            text.push_str(w);
+        }
+        if text.len() >= DOC_TEXT_MAX_BYTES {
+            let mut end = DOC_TEXT_MAX_BYTES;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+            break;
+        }
+    }
+    text
+}
+
 /// Parse the NAME line into (command names, description).
 fn parse_name_line(line: &str) -> (Vec<String>, String) {
     let dash_pos = line.find(" - ").or_else(|| line.find(" \u{2013} "));
@@ -227,5 +289,8 @@ pub fn parse_doc(path: &Path, fname: &str, stemmer: &Stemmer) -> Option<DocField
         synopsis_len,
         body_tf,
         body_len,
+        text: normalise_text(&content),
+        refs: extract_refs(&content),
+        examples: Vec::new(),
     })
 }
@@ -0,0 +1,233 @@
+//! Dense-vector retrieval from a static word-embedding model
+//! (word2vec / GloVe / fastText `.vec` text format).
+//!
+//! The model is only needed at index time: page vectors and the vectors of
+//! every dictionary stem are written to the index, so queries are embedded
+//! from the index alone.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor};
+
+use crate::constants::*;
+use crate::index::{DocVectors, Index, MmapIndex};
+use crate::io_util::{read_str, read_u32};
+use crate::text::{make_stemmer, tokenize};
+
+/// Word vectors loaded from a text model file.
+pub struct WordVectors {
+    pub dim: usize,
+    pub vectors: HashMap<String, Vec<f32>>,
+}
+
+impl WordVectors {
+    /// Reads `word v1 v2 ... vd` lines; an optional `count dim` header line is skipped.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut dim = 0;
+        let mut vectors = HashMap::new();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let Some(word) = parts.next() else {
+                continue;
+            };
+            let values: Vec<f32> = parts.filter_map(|p| p.parse().ok()).collect();
+            if i == 0 && values.len() == 1 {
+                continue; // "count dim" header
+            }
+            if dim == 0 {
+                dim = values.len();
+            }
+            if values.len() != dim || dim == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{path}:{}: expected {dim} values", i + 1),
+                ));
+            }
+            vectors.insert(word.to_lowercase(), values);
+        }
+        Ok(WordVectors { dim, vectors })
+    }
+}
+
+fn normalise(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+fn idf(df: usize, n: f32) -> f32 {
+    let df = df.max(1) as f32;
+    ((n - df + 0.5) / (df + 0.5) + 1.0).ln().max(0.01)
+}
+
+/// Computes a unit vector per document as the IDF-weighted mean of the
+/// vectors of its NAME/synopsis terms, plus a vector per dictionary stem
+/// (the mean over the model words sharing that stem).
+pub fn embed_index(index: &Index, model: &WordVectors) -> DocVectors {
+    let stemmer = make_stemmer();
+    let dim = model.dim;
+
+    let mut sums: HashMap<String, (Vec<f32>, u32)> = HashMap::new();
+    for (word, vec) in &model.vectors {
+        let stem = stemmer.stem(word).into_owned();
+        if !index.inverted.contains_key(&stem) {
+            continue;
+        }
+        let entry = sums.entry(stem).or_insert_with(|| (vec![0.0; dim], 0));
+        entry.0.iter_mut().zip(vec).for_each(|(a, b)| *a += b);
+        entry.1 += 1;
+    }
+    let mut terms: Vec<(String, Vec<f32>)> = sums
+        .into_iter()
+        .map(|(stem, (mut v, _))| {
+            normalise(&mut v);
+            (stem, v)
+        })
+        .collect();
+    terms.sort_by(|a, b| a.0.cmp(&b.0));
+    let lookup: HashMap<&str, &[f32]> = terms
+        .iter()
+        .map(|(t, v)| (t.as_str(), v.as_slice()))
+        .collect();
+
+    let n = index.doc_map.len() as f32;
+    let mut docs = vec![0.0f32; index.doc_map.len() * dim];
+    for (doc_id, field_terms) in index.field_terms.iter().enumerate() {
+        let doc_vec = &mut docs[doc_id * dim..(doc_id + 1) * dim];
+        for term in field_terms {
+            if let Some(v) = lookup.get(term.as_str()) {
+                let w = idf(index.inverted.get(term).map_or(0, Vec::len), n);
+                doc_vec
+                    .iter_mut()
+                    .zip(v.iter())
+                    .for_each(|(a, b)| *a += w * b);
+            }
+        }
+        normalise(doc_vec);
+    }
+
+    DocVectors { dim, docs, terms }
+}
+
+/// Read-only view of the vector section of a loaded index.
+pub struct VectorIndex {
+    dim: usize,
+    docs_offset: usize,
+    doc_count: usize,
+    terms: HashMap<String, usize>, // stem -> byte offset of its vector
+}
+
+impl VectorIndex {
+    /// Parses the section header and term table; returns `None` when the
+    /// index was built without embeddings. Every vector must lie within
+    /// `bytes`, which starts at `base` in the file, and there must be one
+    /// per document of the segment.
+    pub fn open(bytes: &[u8], base: usize, expected_docs: usize) -> io::Result<Option<Self>> {
+        let out_of_bounds = || io::Error::new(io::ErrorKind::InvalidData, "Vector section out of bounds");
+        // Position just past `len` more bytes, if they are all in the section
+        let skip = |from: u64, len: Option<usize>| {
+            len.and_then(|len| (from as usize).checked_add(len))
+                .filter(|&end| end <= bytes.len())
+                .ok_or_else(out_of_bounds)
+        };
+
+        let mut r = Cursor::new(bytes);
+        let dim = read_u32(&mut r)? as usize;
+        if dim == 0 {
+            return Ok(None);
+        }
+        let doc_count = read_u32(&mut r)? as usize;
+        if doc_count != expected_docs {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{doc_count} document vectors for {expected_docs} documents"),
+            ));
+        }
+        let vec_bytes = dim.checked_mul(4);
+        let docs_offset = base + r.position() as usize;
+        let docs_end = skip(r.position(), vec_bytes.and_then(|n| n.checked_mul(doc_count)))?;
+        r.set_position(docs_end as u64);
+
+        let n_terms = read_u32(&mut r)? as usize;
+        let mut terms = HashMap::with_capacity(n_terms.min(bytes.len()));
+        for _ in 0..n_terms {
+            let term = read_str(&mut r)?;
+            terms.insert(term, base + r.position() as usize);
+            let end = skip(r.position(), vec_bytes)?;
+            r.set_position(end as u64);
+        }
+        Ok(Some(VectorIndex {
+            dim,
+            docs_offset,
+            doc_count,
+            terms,
+        }))
+    }
+
+    fn read_vec(&self, raw: &[u8], offset: usize, out: &mut [f32]) {
+        for (i, x) in out.iter_mut().enumerate() {
+            let p = offset + i * 4;
+            *x = f32::from_le_bytes([raw[p], raw[p + 1], raw[p + 2], raw[p + 3]]);
+        }
+    }
+
+    /// Embeds a query the same way pages were embedded at index time.
+    pub fn embed_query(&self, query: &str, index: &MmapIndex) -> Option<Vec<f32>> {
+        let stemmer = make_stemmer();
+        let n = index.doc_map.len() as f32;
+        let raw = index.raw();
+        let mut q = vec![0.0f32; self.dim];
+        let mut tmp = vec![0.0f32; self.dim];
+        let mut found = false;
+        for token in tokenize(query, &stemmer) {
+            let Some(&offset) = self.terms.get(&token) else {
+                continue;
+            };
+            self.read_vec(raw, offset, &mut tmp);
+            let w = idf(index.df(&token) as usize, n);
+            q.iter_mut().zip(&tmp).for_each(|(a, b)| *a += w * b);
+            found = true;
+        }
+        if !found {
+            return None;
+        }
+        normalise(&mut q);
+        Some(q)
+    }
+
+    /// Brute-force nearest neighbours by cosine similarity (vectors are unit length).
+    pub fn nearest(&self, query_vec: &[f32], index: &MmapIndex, k: usize) -> Vec<(u32, f32)> {
+        let raw = index.raw();
+        let mut doc_vec = vec![0.0f32; self.dim];
+        let mut scored: Vec<(u32, f32)> = (0..self.doc_count)
+            .map(|doc_id| {
+                self.read_vec(raw, self.docs_offset + doc_id * self.dim * 4, &mut doc_vec);
+                let sim: f32 = doc_vec.iter().zip(query_vec).map(|(a, b)| a * b).sum();
+                (doc_id as u32, sim)
+            })
+            .filter(|&(_, sim)| sim > 0.0)
+            .collect();
+        scored.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        scored.truncate(k);
+        scored
+    }
+}
+
+/// Reciprocal rank fusion: each list contributes `1 / (RRF_K + rank)` per document.
+pub fn reciprocal_rank_fusion(lists: &[&[(u32, f32)]]) -> Vec<(u32, f32)> {
+    let mut fused: HashMap<u32, f32> = HashMap::new();
+    for list in lists {
+        for (rank, &(doc_id, _)) in list.iter().enumerate() {
+            *fused.entry(doc_id).or_insert(0.0) += 1.0 / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+    fused.into_iter().collect()
+}
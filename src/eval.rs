@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use serde_json::Value;
+
+use crate::index::MmapIndex;
+use crate::search::search;
+
+/// Relevance grades for one query: fname (or bare command name) -> grade.
+pub struct QueryJudgments {
+    pub query: String,
+    pub grades: HashMap<String, u32>,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct QueryMetrics {
+    /// Reciprocal rank of the first relevant result (0 if none).
+    pub rr: f32,
+    pub ndcg: f32,
+    pub recall: f32,
+}
+
+impl QueryMetrics {
+    pub fn mean(all: &[QueryMetrics]) -> QueryMetrics {
+        let n = all.len().max(1) as f32;
+        QueryMetrics {
+            rr: all.iter().map(|m| m.rr).sum::<f32>() / n,
+            ndcg: all.iter().map(|m| m.ndcg).sum::<f32>() / n,
+            recall: all.iter().map(|m| m.recall).sum::<f32>() / n,
+        }
+    }
+}
+
+/// Reads a judgments file: one `query<TAB>fname[<TAB>grade]` per line,
+/// `#` comments allowed. A missing grade means 1; grade 0 marks a result
+/// as judged non-relevant. `fname` may be a bare command name ("mkdir") to
+/// accept any section. Queries keep their first-seen order.
+pub fn load_judgments(path: &str) -> io::Result<Vec<QueryJudgments>> {
+    let content = fs::read_to_string(path)?;
+    let mut judgments: Vec<QueryJudgments> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split('\t').map(str::trim);
+        let (Some(query), Some(fname)) = (fields.next(), fields.next()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path}:{}: expected `query<TAB>fname[<TAB>grade]`", i + 1),
+            ));
+        };
+        let grade = match fields.next() {
+            Some(g) => g.parse::<u32>().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{path}:{}: bad grade `{g}`", i + 1),
+                )
+            })?,
+            None => 1,
+        };
+
+        let pos = *positions.entry(query.to_string()).or_insert_with(|| {
+            judgments.push(QueryJudgments {
+                query: query.to_string(),
+                grades: HashMap::new(),
+            });
+            judgments.len() - 1
+        });
+        judgments[pos].grades.insert(fname.to_string(), grade);
+    }
+    Ok(judgments)
+}
+
+/// Derives judgments from a server query log: every page opened from a
+/// query's results counts as relevant, graded by how often it was opened
+/// (capped at 3). Searches and opens without a query are ignored.
+pub fn judgments_from_log(path: &str) -> io::Result<Vec<QueryJudgments>> {
+    let content = fs::read_to_string(path)?;
+    let mut judgments: Vec<QueryJudgments> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Value = serde_json::from_str(line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{path}:{}: {e}", i + 1))
+        })?;
+        if entry["event"] != "open" {
+            continue;
+        }
+        let (Some(query), Some(fname)) = (entry["query"].as_str(), entry["fname"].as_str()) else {
+            continue;
+        };
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            continue;
+        }
+
+        let pos = *positions.entry(query.clone()).or_insert_with(|| {
+            judgments.push(QueryJudgments {
+                query,
+                grades: HashMap::new(),
+            });
+            judgments.len() - 1
+        });
+        let grade = judgments[pos].grades.entry(fname.to_string()).or_insert(0);
+        *grade = (*grade + 1).min(3);
+    }
+    Ok(judgments)
+}
+
+/// The judgment key `fname` satisfies, preferring an exact fname match.
+fn judged_key<'a>(fname: &'a str, grades: &HashMap<String, u32>) -> Option<&'a str> {
+    if grades.contains_key(fname) {
+        return Some(fname);
+    }
+    let base = fname.split('.').next().unwrap_or(fname);
+    grades.contains_key(base).then_some(base)
+}
+
+/// Scores a ranked list of fnames against `grades`: reciprocal rank,
+/// nDCG@10 (gain `2^grade - 1`) and recall at `k`.
+pub fn evaluate(ranked: &[String], grades: &HashMap<String, u32>, k: usize) -> QueryMetrics {
+    let relevant = grades.values().filter(|&&g| g > 0).count();
+    if relevant == 0 {
+        return QueryMetrics::default();
+    }
+
+    let mut metrics = QueryMetrics::default();
+    let mut dcg = 0.0f32;
+    let mut found_at_k = 0usize;
+    let mut seen: Vec<&str> = Vec::new();
+
+    for (i, fname) in ranked.iter().enumerate() {
+        let Some(key) = judged_key(fname, grades) else {
+            continue;
+        };
+        // A bare command name is satisfied once, however many sections match
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        let grade = grades[key];
+        if grade == 0 {
+            continue;
+        }
+        if metrics.rr == 0.0 {
+            metrics.rr = 1.0 / (i + 1) as f32;
+        }
+        if i < 10 {
+            dcg += ((1u32 << grade.min(16)) - 1) as f32 / ((i + 2) as f32).log2();
+        }
+        if i < k {
+            found_at_k += 1;
+        }
+    }
+
+    let mut ideal: Vec<u32> = grades.values().copied().filter(|&g| g > 0).collect();
+    ideal.sort_unstable_by(|a, b| b.cmp(a));
+    let idcg: f32 = ideal
+        .iter()
+        .take(10)
+        .enumerate()
+        .map(|(i, &g)| ((1u32 << g.min(16)) - 1) as f32 / ((i + 2) as f32).log2())
+        .sum();
+
+    metrics.ndcg = if idcg > 0.0 { dcg / idcg } else { 0.0 };
+    metrics.recall = found_at_k as f32 / relevant as f32;
+    metrics
+}
+
+/// Runs every judged query against `index`.
+pub fn evaluate_index(
+    judgments: &[QueryJudgments],
+    index: &MmapIndex,
+    k: usize,
+) -> Vec<QueryMetrics> {
+    judgments
+        .iter()
+        .map(|j| {
+            let ranked: Vec<String> = search(&j.query, index)
+                .into_iter()
+                .map(|r| r.fname)
+                .collect();
+            evaluate(&ranked, &j.grades, k)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranked(fnames: &[&str]) -> Vec<String> {
+        fnames.iter().map(|f| f.to_string()).collect()
+    }
+
+    fn grades(pairs: &[(&str, u32)]) -> HashMap<String, u32> {
+        pairs.iter().map(|&(f, g)| (f.to_string(), g)).collect()
+    }
+
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn perfect_ranking_scores_one() {
+        let g = grades(&[("cp.1", 2), ("mv.1", 1)]);
+        let m = evaluate(&ranked(&["cp.1", "mv.1", "ln.1"]), &g, 10);
+        assert_eq!((m.rr, m.recall), (1.0, 1.0));
+        assert!((m.ndcg - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn scores_a_late_hit() {
+        let g = grades(&[("mv.1", 1)]);
+        let m = evaluate(&ranked(&["cp.1", "ln.1", "mv.1"]), &g, 2);
+        assert_eq!((m.rr, m.recall), (1.0 / 3.0, 0.0));
+        assert!((m.ndcg - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn bare_names_match_once_and_zero_grades_do_not_count() {
+        let g = grades(&[("printf", 1), ("echo.1", 0)]);
+        let m = evaluate(&ranked(&["echo.1", "printf.1", "printf.3"]), &g, 10);
+        assert_eq!((m.rr, m.recall), (0.5, 1.0));
+        let g = grades(&[("echo.1", 0)]);
+        assert_eq!(evaluate(&ranked(&["echo.1"]), &g, 10).rr, 0.0);
+    }
+
+    #[test]
+    fn loads_judgments_in_query_order() {
+        let content = "# comment\ncopy\tcp.1\t2\nmove\tmv\ncopy\tinstall.1\t0\n";
+        let path = temp_file("judgments.tsv", content);
+        let judgments = load_judgments(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let queries: Vec<&str> = judgments.iter().map(|j| j.query.as_str()).collect();
+        assert_eq!(queries, ["copy", "move"]);
+        let copy = grades(&[("cp.1", 2), ("install.1", 0)]);
+        assert_eq!(judgments[0].grades, copy);
+        assert_eq!(judgments[1].grades, grades(&[("mv", 1)]));
+    }
+
+    #[test]
+    fn rejects_a_bad_grade() {
+        let path = temp_file("bad-judgments.tsv", "copy\tcp.1\thigh\n");
+        let result = load_judgments(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn grades_log_opens_by_count() {
+        let open = r#"{"event":"open","query":" Copy ","fname":"cp.1"}"#;
+        let search = r#"{"event":"search","query":"copy","fname":"mv.1"}"#;
+        let content = [open, open, open, open, search].join("\n");
+        let path = temp_file("queries.log", &content);
+        let judgments = judgments_from_log(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(judgments.len(), 1);
+        assert_eq!(judgments[0].query, "copy");
+        assert_eq!(judgments[0].grades, grades(&[("cp.1", 3)]));
+    }
+}
@@ -0,0 +1,614 @@
+//! Dumps a loaded index to JSON Lines or SQLite for analysis outside this
+//! crate, and builds an `Index` back from such a dump.
+//!
+//! JSON Lines holds one record per line, tagged by `kind`:
+//!   {"kind":"doc","doc_id":0,"fname":"ls.1","name":"ls","section":"1",…}
+//!   {"kind":"term","term":"copi","postings":[[12,[0.0,1.9,0.4,2.2]],…]}
+//!   {"kind":"association","term":"copi","related":[["cp",0.61],…]}
+//! Postings hold the unweighted command-name, description, synopsis and
+//! body BM25 parts, as in the index.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assoc::{mine_associations, Associations};
+use crate::graph::split_page;
+use crate::index::{build_term_vectors, sort_postings, FieldScores, Index, MmapIndex};
+use crate::profile::RankingProfile;
+use crate::text::{make_stemmer, tokenize};
+use crate::tldr::Example;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Jsonl,
+    Sqlite,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(ExportFormat::Jsonl),
+            "sqlite" => Ok(ExportFormat::Sqlite),
+            _ => Err(format!("unknown format `{s}` (expected jsonl or sqlite)")),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// SQLite for `.db`, `.sqlite` and `.sqlite3` files, JSON Lines otherwise.
+    pub fn from_path(path: &str) -> Self {
+        let ext = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+        if matches!(ext, "db" | "sqlite" | "sqlite3") {
+            ExportFormat::Sqlite
+        } else {
+            ExportFormat::Jsonl
+        }
+    }
+}
+
+/// What goes into an export besides the documents.
+#[derive(Clone, Copy, Default)]
+pub struct ExportOptions {
+    /// The inverted index and term associations, needed to import it again.
+    pub postings: bool,
+    /// The stored page text of every document.
+    pub text: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DocRecord {
+    pub doc_id: u32,
+    pub fname: String,
+    pub name: String,
+    pub section: String,
+    /// Collection the page was crawled from.
+    #[serde(default)]
+    pub source: String,
+    /// Stemmed command name from the NAME line.
+    pub cmd_name: String,
+    pub description: String,
+    pub desc_len: u32,
+    pub synopsis_len: u32,
+    pub body_len: u32,
+    pub authority: f32,
+    /// SEE ALSO targets, as fnames.
+    #[serde(default)]
+    pub see_also: Vec<String>,
+    /// Example commands of a tldr page.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Example>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TermRecord {
+    pub term: String,
+    pub postings: Vec<(u32, FieldScores)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AssociationRecord {
+    pub term: String,
+    pub related: Vec<(String, f32)>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Record {
+    Doc(DocRecord),
+    Term(TermRecord),
+    Association(AssociationRecord),
+}
+
+/// Everything read back from an export.
+#[derive(Default)]
+pub struct Export {
+    pub docs: Vec<DocRecord>,
+    pub terms: Vec<TermRecord>,
+    pub associations: Vec<AssociationRecord>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn doc_record(index: &MmapIndex, doc_id: u32, text: bool) -> DocRecord {
+    let i = doc_id as usize;
+    let fname = &index.doc_map[i];
+    let (name, section) = split_page(fname).unwrap_or((fname, ""));
+    let [desc_len, synopsis_len, body_len] = index.field_lens[i];
+    DocRecord {
+        doc_id,
+        fname: fname.clone(),
+        name: name.to_string(),
+        section: section.to_string(),
+        source: index.sources[i].clone(),
+        cmd_name: index.cmd_names[i].clone(),
+        description: index.name_descs[i].clone(),
+        desc_len,
+        synopsis_len,
+        body_len,
+        authority: index.authority[i],
+        see_also: index.see_also[i]
+            .iter()
+            .map(|&t| index.doc_map[t as usize].clone())
+            .collect(),
+        examples: index.examples[i].clone(),
+        text: text.then(|| index.doc_text(doc_id).to_string()),
+    }
+}
+
+/// Dictionary terms in sorted order, so exports of one index are identical.
+pub fn sorted_terms(index: &MmapIndex) -> Vec<&str> {
+    let mut terms: Vec<&str> = index.inverted_dict.keys().map(String::as_str).collect();
+    terms.sort_unstable();
+    terms
+}
+
+fn write_record(out: &mut impl Write, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    writeln!(out)
+}
+
+pub fn write_jsonl(index: &MmapIndex, options: ExportOptions, mut out: impl Write) -> io::Result<()> {
+    for doc_id in 0..index.doc_map.len() as u32 {
+        write_record(&mut out, &Record::Doc(doc_record(index, doc_id, options.text)))?;
+    }
+    if options.postings {
+        for term in sorted_terms(index) {
+            let postings = index.get_field_postings(term).unwrap_or_default();
+            let record = Record::Term(TermRecord {
+                term: term.to_string(),
+                postings,
+            });
+            write_record(&mut out, &record)?;
+        }
+        let mut assoc: Vec<(&String, &Vec<(String, f32)>)> = index.associations.iter().collect();
+        assoc.sort_by(|a, b| a.0.cmp(b.0));
+        for (term, related) in assoc {
+            let record = Record::Association(AssociationRecord {
+                term: term.clone(),
+                related: related.clone(),
+            });
+            write_record(&mut out, &record)?;
+        }
+    }
+    out.flush()
+}
+
+pub fn read_jsonl(input: impl BufRead) -> io::Result<Export> {
+    let mut export = Export::default();
+    for (lineno, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)
+            .map_err(|e| invalid(format!("line {}: {e}", lineno + 1)))?;
+        match record {
+            Record::Doc(doc) => export.docs.push(doc),
+            Record::Term(term) => export.terms.push(term),
+            Record::Association(assoc) => export.associations.push(assoc),
+        }
+    }
+    Ok(export)
+}
+
+/// Builds an index from `export`, which must contain postings. Doc ids are
+/// renumbered in document order; rows may be dropped or edited freely as
+/// long as postings only name exported documents. Associations are mined
+/// again when the export has none. Authority is taken as exported, not
+/// recomputed: the links it was computed from are not all in the dump.
+/// Embeddings are not exported.
+pub fn build_from_export(export: Export) -> io::Result<Index> {
+    if export.terms.is_empty() {
+        return Err(invalid(
+            "the export has no postings (export it with --postings)".to_string(),
+        ));
+    }
+
+    let mut new_id: HashMap<u32, u32> = HashMap::with_capacity(export.docs.len());
+    for (i, doc) in export.docs.iter().enumerate() {
+        if new_id.insert(doc.doc_id, i as u32).is_some() {
+            return Err(invalid(format!("doc id {} appears twice", doc.doc_id)));
+        }
+    }
+    let by_fname: HashMap<&str, u32> = export
+        .docs
+        .iter()
+        .enumerate()
+        .map(|(i, doc)| (doc.fname.as_str(), i as u32))
+        .collect();
+
+    let mut inverted: HashMap<String, Vec<(u32, FieldScores)>> =
+        HashMap::with_capacity(export.terms.len());
+    for record in export.terms {
+        let mut postings = Vec::with_capacity(record.postings.len());
+        for (doc_id, parts) in record.postings {
+            let id = new_id.get(&doc_id).ok_or_else(|| {
+                invalid(format!("`{}` lists unknown doc {doc_id}", record.term))
+            })?;
+            postings.push((*id, parts));
+        }
+        if !postings.is_empty() {
+            inverted.entry(record.term).or_default().extend(postings);
+        }
+    }
+
+    let doc_map: Vec<String> = export.docs.iter().map(|d| d.fname.clone()).collect();
+    sort_postings(&mut inverted, &doc_map);
+
+    // Most telling terms per document: command name, description, then synopsis
+    let stemmer = make_stemmer();
+    let mut synopsis_terms: Vec<Vec<(f32, &String)>> = vec![Vec::new(); doc_map.len()];
+    for (term, postings) in &inverted {
+        for (doc_id, parts) in postings {
+            if parts[2] > 0.0 {
+                synopsis_terms[*doc_id as usize].push((parts[2], term));
+            }
+        }
+    }
+    let mut cmd_name_index: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut desc_index: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut field_terms: Vec<Vec<String>> = Vec::with_capacity(doc_map.len());
+    for (doc_id, doc) in export.docs.iter().enumerate() {
+        let mut terms: Vec<String> = Vec::new();
+        if !doc.cmd_name.is_empty() {
+            cmd_name_index
+                .entry(doc.cmd_name.clone())
+                .or_default()
+                .push(doc_id as u32);
+            terms.push(doc.cmd_name.clone());
+        }
+        let mut desc_terms = tokenize(&doc.description, &stemmer);
+        desc_terms.sort();
+        desc_terms.dedup();
+        for term in &desc_terms {
+            desc_index.entry(term.clone()).or_default().push(doc_id as u32);
+        }
+        terms.extend(desc_terms);
+        let synopsis = &mut synopsis_terms[doc_id];
+        synopsis.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        terms.extend(synopsis.iter().map(|(_, t)| (*t).clone()));
+        field_terms.push(terms);
+    }
+
+    let associations: Associations = if export.associations.is_empty() {
+        mine_associations(&field_terms)
+    } else {
+        export
+            .associations
+            .into_iter()
+            .map(|a| (a.term, a.related))
+            .collect()
+    };
+
+    let see_also: Vec<Vec<u32>> = export
+        .docs
+        .iter()
+        .map(|doc| {
+            doc.see_also
+                .iter()
+                .filter_map(|fname| by_fname.get(fname.as_str()).copied())
+                .collect()
+        })
+        .collect();
+    let term_vectors = build_term_vectors(&inverted, doc_map.len(), &RankingProfile::default());
+
+    let mut sources = Vec::with_capacity(doc_map.len());
+    let mut cmd_names = Vec::with_capacity(doc_map.len());
+    let mut name_descs = Vec::with_capacity(doc_map.len());
+    let mut field_lens = Vec::with_capacity(doc_map.len());
+    let mut authority = Vec::with_capacity(doc_map.len());
+    let mut texts = Vec::with_capacity(doc_map.len());
+    let mut examples = Vec::with_capacity(doc_map.len());
+    for doc in export.docs {
+        sources.push(doc.source);
+        cmd_names.push(doc.cmd_name);
+        name_descs.push(doc.description);
+        field_lens.push([doc.desc_len, doc.synopsis_len, doc.body_len]);
+        authority.push(doc.authority);
+        texts.push(doc.text.unwrap_or_default());
+        examples.push(doc.examples);
+    }
+
+    Ok(Index {
+        doc_map,
+        sources,
+        cmd_names,
+        name_descs,
+        field_lens,
+        texts,
+        inverted,
+        cmd_name_index,
+        desc_index,
+        field_terms,
+        associations,
+        // Inline mentions are not exported; authority already accounts for them
+        links: see_also.clone(),
+        authority,
+        see_also,
+        term_vectors,
+        examples,
+        vectors: None,
+    })
+}
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite {
+    //! The same records as tables: `docs`, `see_also`, `examples`,
+    //! `postings` and `associations`, joined on `doc_id` and `term`.
+
+    use std::fs;
+    use std::io;
+
+    use rusqlite::{params, Connection};
+
+    use super::*;
+
+    const SCHEMA: &str = "
+        CREATE TABLE docs (
+            doc_id       INTEGER PRIMARY KEY,
+            fname        TEXT NOT NULL,
+            name         TEXT NOT NULL,
+            section      TEXT NOT NULL,
+            source       TEXT NOT NULL,
+            cmd_name     TEXT NOT NULL,
+            description  TEXT NOT NULL,
+            desc_len     INTEGER NOT NULL,
+            synopsis_len INTEGER NOT NULL,
+            body_len     INTEGER NOT NULL,
+            authority    REAL NOT NULL,
+            text         TEXT
+        );
+        CREATE TABLE see_also (
+            doc_id    INTEGER NOT NULL,
+            position  INTEGER NOT NULL,
+            target_id INTEGER NOT NULL
+        );
+        CREATE TABLE examples (
+            doc_id      INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            command     TEXT NOT NULL
+        );
+        CREATE TABLE postings (
+            term        TEXT NOT NULL,
+            doc_id      INTEGER NOT NULL,
+            cmd_name    REAL NOT NULL,
+            description REAL NOT NULL,
+            synopsis    REAL NOT NULL,
+            body        REAL NOT NULL
+        );
+        CREATE TABLE associations (
+            term     TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            related  TEXT NOT NULL,
+            weight   REAL NOT NULL
+        );";
+
+    /// Indexes created after loading, which is much faster than before.
+    const INDEXES: &str = "
+        CREATE INDEX postings_term ON postings (term);
+        CREATE INDEX postings_doc ON postings (doc_id);
+        CREATE INDEX see_also_doc ON see_also (doc_id);
+        CREATE INDEX examples_doc ON examples (doc_id);
+        CREATE INDEX associations_term ON associations (term);";
+
+    fn db_error(e: rusqlite::Error) -> io::Error {
+        io::Error::other(e)
+    }
+
+    /// Writes a new database at `path`, replacing any file already there.
+    pub fn write_sqlite(index: &MmapIndex, options: ExportOptions, path: &str) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut conn = Connection::open(path).map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        let tx = conn.transaction().map_err(db_error)?;
+        {
+            let mut insert_doc = tx
+                .prepare("INSERT INTO docs VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)")
+                .map_err(db_error)?;
+            let mut insert_link = tx
+                .prepare("INSERT INTO see_also VALUES (?1, ?2, ?3)")
+                .map_err(db_error)?;
+            let mut insert_example = tx
+                .prepare("INSERT INTO examples VALUES (?1, ?2, ?3, ?4)")
+                .map_err(db_error)?;
+            for doc_id in 0..index.doc_map.len() as u32 {
+                let d = doc_record(index, doc_id, options.text);
+                insert_doc
+                    .execute(params![
+                        d.doc_id,
+                        d.fname,
+                        d.name,
+                        d.section,
+                        d.source,
+                        d.cmd_name,
+                        d.description,
+                        d.desc_len,
+                        d.synopsis_len,
+                        d.body_len,
+                        d.authority,
+                        d.text
+                    ])
+                    .map_err(db_error)?;
+                for (position, &target) in index.see_also[doc_id as usize].iter().enumerate() {
+                    insert_link
+                        .execute(params![doc_id, position as u32, target])
+                        .map_err(db_error)?;
+                }
+                for (position, example) in d.examples.iter().enumerate() {
+                    insert_example
+                        .execute(params![
+                            doc_id,
+                            position as u32,
+                            example.description,
+                            example.command
+                        ])
+                        .map_err(db_error)?;
+                }
+            }
+
+            if options.postings {
+                let mut insert_posting = tx
+                    .prepare("INSERT INTO postings VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+                    .map_err(db_error)?;
+                for term in sorted_terms(index) {
+                    for (doc_id, p) in index.get_field_postings(term).unwrap_or_default() {
+                        insert_posting
+                            .execute(params![term, doc_id, p[0], p[1], p[2], p[3]])
+                            .map_err(db_error)?;
+                    }
+                }
+                let mut insert_assoc = tx
+                    .prepare("INSERT INTO associations VALUES (?1, ?2, ?3, ?4)")
+                    .map_err(db_error)?;
+                let mut assoc: Vec<(&String, &Vec<(String, f32)>)> =
+                    index.associations.iter().collect();
+                assoc.sort_by(|a, b| a.0.cmp(b.0));
+                for (term, related) in assoc {
+                    for (position, (r, weight)) in related.iter().enumerate() {
+                        insert_assoc
+                            .execute(params![term, position as u32, r, weight])
+                            .map_err(db_error)?;
+                    }
+                }
+            }
+        }
+        tx.commit().map_err(db_error)?;
+        conn.execute_batch(INDEXES).map_err(db_error)
+    }
+
+    pub fn read_sqlite(path: &str) -> io::Result<Export> {
+        let conn = Connection::open(path).map_err(db_error)?;
+        let mut export = Export::default();
+
+        let mut links: HashMap<u32, Vec<String>> = HashMap::new();
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.doc_id, d.fname FROM see_also s JOIN docs d ON d.doc_id = s.target_id \
+                 ORDER BY s.doc_id, s.position",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?)))
+            .map_err(db_error)?;
+        for row in rows {
+            let (doc_id, target) = row.map_err(db_error)?;
+            links.entry(doc_id).or_default().push(target);
+        }
+
+        let mut examples: HashMap<u32, Vec<Example>> = HashMap::new();
+        let mut stmt = conn
+            .prepare("SELECT doc_id, description, command FROM examples ORDER BY doc_id, position")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    Example {
+                        description: row.get(1)?,
+                        command: row.get(2)?,
+                    },
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (doc_id, example) = row.map_err(db_error)?;
+            examples.entry(doc_id).or_default().push(example);
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT doc_id, fname, name, section, source, cmd_name, description, \
+                 desc_len, synopsis_len, body_len, authority, text FROM docs ORDER BY doc_id",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(DocRecord {
+                    doc_id: row.get(0)?,
+                    fname: row.get(1)?,
+                    name: row.get(2)?,
+                    section: row.get(3)?,
+                    source: row.get(4)?,
+                    cmd_name: row.get(5)?,
+                    description: row.get(6)?,
+                    desc_len: row.get(7)?,
+                    synopsis_len: row.get(8)?,
+                    body_len: row.get(9)?,
+                    authority: row.get(10)?,
+                    see_also: Vec::new(),
+                    examples: Vec::new(),
+                    text: row.get(11)?,
+                })
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let mut doc = row.map_err(db_error)?;
+            doc.see_also = links.remove(&doc.doc_id).unwrap_or_default();
+            doc.examples = examples.remove(&doc.doc_id).unwrap_or_default();
+            export.docs.push(doc);
+        }
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT term, doc_id, cmd_name, description, synopsis, body FROM postings \
+                 ORDER BY term",
+            )
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, u32>(1)?,
+                    [row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?],
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (term, doc_id, parts) = row.map_err(db_error)?;
+            match export.terms.last_mut() {
+                Some(last) if last.term == term => last.postings.push((doc_id, parts)),
+                _ => export.terms.push(TermRecord {
+                    term,
+                    postings: vec![(doc_id, parts)],
+                }),
+            }
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT term, related, weight FROM associations ORDER BY term, position")
+            .map_err(db_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f32>(2)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (term, related, weight) = row.map_err(db_error)?;
+            match export.associations.last_mut() {
+                Some(last) if last.term == term => last.related.push((related, weight)),
+                _ => export.associations.push(AssociationRecord {
+                    term,
+                    related: vec![(related, weight)],
+                }),
+            }
+        }
+        Ok(export)
+    }
+}
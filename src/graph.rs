@@ -0,0 +1,236 @@
+//! Cross-reference graph between pages (`name(section)` mentions) and a
+//! PageRank authority score computed over it.
+
+use std::collections::HashMap;
+
+use crate::constants::{PAGERANK_DAMPING, PAGERANK_ITERATIONS};
+
+/// References found in one rendered page, as `name.section` strings.
+#[derive(Default)]
+pub struct PageRefs {
+    /// Targets listed under the SEE ALSO heading.
+    pub see_also: Vec<String>,
+    /// Every other `name(section)` mention in the page.
+    pub mentions: Vec<String>,
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.' | b':' | b'+' | b'@')
+}
+
+/// Finds every `name(section)` reference in `line`, e.g. `kill(2)` or
+/// `systemd.unit(5)`, returned as lowercase `name.section`.
+fn refs_in_line(line: &str) -> Vec<String> {
+    let bytes = line.as_bytes();
+    let mut refs = Vec::new();
+
+    for (open, _) in line.match_indices('(') {
+        // Section: a digit, up to three more alphanumerics, then ')'
+        let sec_start = open + 1;
+        let mut sec_end = sec_start;
+        while sec_end < bytes.len()
+            && sec_end - sec_start < 4
+            && bytes[sec_end].is_ascii_alphanumeric()
+        {
+            sec_end += 1;
+        }
+        if sec_end == sec_start
+            || !bytes[sec_start].is_ascii_digit()
+            || bytes[sec_start] == b'0'
+            || bytes.get(sec_end) != Some(&b')')
+        {
+            continue;
+        }
+
+        let mut name_start = open;
+        while name_start > 0 && is_name_byte(bytes[name_start - 1]) {
+            name_start -= 1;
+        }
+        let name = line[name_start..open]
+            .trim_start_matches(|c: char| !c.is_ascii_alphanumeric() && c != '_');
+        if name.is_empty() || name.len() > 64 {
+            continue;
+        }
+        refs.push(format!("{}.{}", name, &line[sec_start..sec_end]).to_lowercase());
+    }
+    refs
+}
+
+/// Collects the references of a rendered man page, telling SEE ALSO entries
+/// apart from inline mentions. Each target appears once.
+pub fn extract_refs(content: &str) -> PageRefs {
+    let mut refs = PageRefs::default();
+    let mut in_see_also = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        // Section headings start in column 0 and have no lowercase letters
+        if !line.starts_with(char::is_whitespace)
+            && trimmed.chars().any(char::is_alphabetic)
+            && trimmed.chars().all(|c| !c.is_lowercase())
+        {
+            in_see_also = trimmed == "SEE ALSO";
+            continue;
+        }
+        for target in refs_in_line(line) {
+            let list = if in_see_also {
+                &mut refs.see_also
+            } else {
+                &mut refs.mentions
+            };
+            if !list.contains(&target) {
+                list.push(target);
+            }
+        }
+    }
+    refs.mentions.retain(|m| !refs.see_also.contains(m));
+    refs
+}
+
+/// `ls.1` -> ("ls", "1"), ignoring a compression suffix.
+pub fn split_page(fname: &str) -> Option<(&str, &str)> {
+    let fname = [".gz", ".bz2", ".xz", ".zst"]
+        .iter()
+        .find_map(|ext| fname.strip_suffix(ext))
+        .unwrap_or(fname);
+    fname.rsplit_once('.')
+}
+
+/// Maps `name.section` references to doc ids. A reference to section `3`
+/// also matches a page filed under `3p` and vice versa when there is no
+/// exact match.
+pub struct PageResolver {
+    exact: HashMap<String, u32>,
+    by_digit: HashMap<(String, char), u32>,
+}
+
+impl PageResolver {
+    pub fn new(doc_map: &[String]) -> Self {
+        let mut exact = HashMap::new();
+        let mut by_digit = HashMap::new();
+        for (doc_id, fname) in doc_map.iter().enumerate() {
+            let Some((name, section)) = split_page(fname) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            let section = section.to_lowercase();
+            if let Some(digit) = section.chars().next() {
+                by_digit
+                    .entry((name.clone(), digit))
+                    .or_insert(doc_id as u32);
+            }
+            exact
+                .entry(format!("{name}.{section}"))
+                .or_insert(doc_id as u32);
+        }
+        PageResolver { exact, by_digit }
+    }
+
+    pub fn resolve(&self, target: &str) -> Option<u32> {
+        if let Some(&doc_id) = self.exact.get(target) {
+            return Some(doc_id);
+        }
+        let (name, section) = target.rsplit_once('.')?;
+        let digit = section.chars().next()?;
+        self.by_digit.get(&(name.to_string(), digit)).copied()
+    }
+
+    /// Resolves `targets` for page `from`, dropping unknown pages,
+    /// self-references and duplicates.
+    pub fn resolve_all(&self, from: u32, targets: &[String]) -> Vec<u32> {
+        let mut ids: Vec<u32> = Vec::new();
+        for doc_id in targets.iter().filter_map(|t| self.resolve(t)) {
+            if doc_id != from && !ids.contains(&doc_id) {
+                ids.push(doc_id);
+            }
+        }
+        ids
+    }
+}
+
+/// PageRank over outgoing `links`, scaled so the mean score is 1.0.
+/// Pages without outgoing links spread their rank evenly over all pages.
+pub fn pagerank(links: &[Vec<u32>]) -> Vec<f32> {
+    let n = links.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let nf = n as f32;
+    let mut rank = vec![1.0 / nf; n];
+    let mut next = vec![0.0f32; n];
+
+    for _ in 0..PAGERANK_ITERATIONS {
+        let dangling: f32 = links
+            .iter()
+            .zip(&rank)
+            .filter(|(out, _)| out.is_empty())
+            .map(|(_, r)| r)
+            .sum();
+        let base = (1.0 - PAGERANK_DAMPING) / nf + PAGERANK_DAMPING * dangling / nf;
+        next.iter_mut().for_each(|r| *r = base);
+        for (from, out) in links.iter().enumerate() {
+            if out.is_empty() {
+                continue;
+            }
+            let share = PAGERANK_DAMPING * rank[from] / out.len() as f32;
+            for &to in out {
+                next[to as usize] += share;
+            }
+        }
+        std::mem::swap(&mut rank, &mut next);
+    }
+
+    rank.iter().map(|r| r * nf).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_references_in_a_line() {
+        let line = "see kill(2), systemd.unit(5) and \"Ls(1p)\"; not f(x), (0) or main(0)";
+        assert_eq!(refs_in_line(line), ["kill.2", "systemd.unit.5", "ls.1p"]);
+    }
+
+    #[test]
+    fn separates_see_also_from_mentions() {
+        let page = "NAME\n     cp - copy, like mv(1)\n\nSEE ALSO\n     mv(1), ln(1), ln(1)\n\n\
+                    BUGS\n     install(1) too\n";
+        let refs = extract_refs(page);
+        assert_eq!(refs.see_also, ["mv.1", "ln.1"]);
+        assert_eq!(refs.mentions, ["install.1"]);
+    }
+
+    #[test]
+    fn splits_page_names() {
+        assert_eq!(split_page("ls.1"), Some(("ls", "1")));
+        assert_eq!(split_page("systemd.unit.5.gz"), Some(("systemd.unit", "5")));
+        assert_eq!(split_page("README"), None);
+    }
+
+    #[test]
+    fn resolves_across_subsections() {
+        let resolver = PageResolver::new(&strings(&["printf.3p", "printf.1", "Kill.2"]));
+        assert_eq!(resolver.resolve("printf.3"), Some(0));
+        assert_eq!(resolver.resolve("printf.1"), Some(1));
+        assert_eq!(resolver.resolve("kill.2"), Some(2));
+        assert_eq!(resolver.resolve("kill.1"), None);
+
+        let targets = strings(&["printf.1", "kill.2", "missing.1", "kill.2"]);
+        assert_eq!(resolver.resolve_all(1, &targets), [2]);
+    }
+
+    #[test]
+    fn pagerank_favours_linked_pages() {
+        let rank = pagerank(&[vec![2], vec![2], vec![]]);
+        let mean = rank.iter().sum::<f32>() / rank.len() as f32;
+        assert!((mean - 1.0).abs() < 1e-4);
+        assert!(rank[2] > rank[0] && (rank[0] - rank[1]).abs() < 1e-6);
+        assert!(pagerank(&[]).is_empty());
+    }
+}
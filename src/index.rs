@@ -2,65 +2,294 @@ use memmap2::MmapOptions;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
 
+use crate::assoc::{mine_associations, Associations};
 use crate::constants::*;
 use crate::crawl::CrawlStats;
 use crate::doc::doc_type_multiplier;
+#[cfg(feature = "embeddings")]
+use crate::embed::VectorIndex;
+use crate::graph::{pagerank, split_page, PageResolver};
+use crate::info::is_info_page;
 use crate::io_util::*;
+use crate::profile::RankingProfile;
+use crate::segment::SegmentList;
+use crate::synonyms::SynonymTable;
+use crate::tldr::{read_examples, write_examples, Example};
+
+/// Unweighted BM25 parts of a posting: command name, NAME description,
+/// synopsis and body. Field weights and the document-type multiplier are
+/// applied at query time from the `RankingProfile`.
+pub type FieldScores = [f32; 4];
+
+/// Token counts of a document's NAME description, synopsis and body.
+pub type FieldLens = [u32; 3];
+
+/// A document's strongest terms with unit-length weights, sorted by term.
+pub type TermVector = Vec<(String, f32)>;
+
+pub(crate) fn weighted(parts: &FieldScores, profile: &RankingProfile) -> f32 {
+    parts[0] * profile.weight_cmd_name
+        + parts[1] * profile.weight_name_desc
+        + parts[2] * profile.weight_synopsis
+        + parts[3] * profile.weight_body
+}
+
+/// Dense vectors written to the index when built with an embedding model.
+pub struct DocVectors {
+    pub dim: usize,
+    /// `doc_count * dim` values, one unit vector per document.
+    pub docs: Vec<f32>,
+    /// Vector of every dictionary stem the model knows, for embedding queries.
+    pub terms: Vec<(String, Vec<f32>)>,
+}
 
 // Used during Pass 2 to build the index in RAM
 pub struct Index {
     pub doc_map: Vec<String>,
+    /// Name of the collection each document was crawled from (`SOURCE_DIRS`).
+    pub sources: Vec<String>,
     pub cmd_names: Vec<String>,
     pub name_descs: Vec<String>,
-    pub inverted: HashMap<String, Vec<(u32, f32)>>,
+    pub field_lens: Vec<FieldLens>,
+    pub texts: Vec<String>,
+    pub inverted: HashMap<String, Vec<(u32, FieldScores)>>,
     pub cmd_name_index: HashMap<String, Vec<u32>>,
     pub desc_index: HashMap<String, Vec<u32>>,
+    /// Command name, description and synopsis terms per document, most telling first.
+    pub field_terms: Vec<Vec<String>>,
+    pub associations: Associations,
+    /// Pages each document references (SEE ALSO and inline `name(section)`).
+    pub links: Vec<Vec<u32>>,
+    /// PageRank over `links`, averaging 1.0.
+    pub authority: Vec<f32>,
+    /// The subset of `links` listed under SEE ALSO, in page order.
+    pub see_also: Vec<Vec<u32>>,
+    pub term_vectors: Vec<TermVector>,
+    /// Example commands per document; only tldr pages have them.
+    pub examples: Vec<Vec<Example>>,
+    pub vectors: Option<DocVectors>,
+}
+
+/// One index file as written by `save_index`. A loaded index is made of
+/// one or more of them (see `crate::segment`).
+pub struct Segment {
+    pub path: String,
+    /// File name of every document in the segment, including deleted ones.
+    pub fnames: Vec<String>,
+    /// Global doc id of each of the segment's documents, `None` once deleted.
+    pub doc_ids: Vec<Option<u32>>,
+    pub(crate) dict: HashMap<String, (u64, u32)>, // word -> (byte_offset, num_postings)
+    pub(crate) text_table: Vec<(u64, u32)>, // local doc id -> (byte_offset, byte_len)
+    pub(crate) mmap: memmap2::Mmap,
+}
+
+impl Segment {
+    pub fn doc_count(&self) -> usize {
+        self.fnames.len()
+    }
+
+    /// Number of terms in the segment's dictionary.
+    pub fn vocabulary(&self) -> usize {
+        self.dict.len()
+    }
+
+    /// Documents that are not deleted.
+    pub fn live_docs(&self) -> usize {
+        self.doc_ids.iter().filter(|id| id.is_some()).count()
+    }
+
+    fn text(&self, local_id: u32) -> &str {
+        let Some(&(offset, len)) = self.text_table.get(local_id as usize) else {
+            return "";
+        };
+        let (start, end) = (offset as usize, offset as usize + len as usize);
+        self.mmap
+            .get(start..end)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .unwrap_or("")
+    }
+
+    /// Reads a posting list straight from the mapped file, with the
+    /// segment's own doc ids and scores.
+    pub(crate) fn read_postings(&self, word: &str) -> Option<Vec<(u32, FieldScores)>> {
+        let &(offset, len) = self.dict.get(word)?;
+        let mut postings = Vec::with_capacity(len as usize);
+        let mut pos = offset as usize;
+
+        for _ in 0..len {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&self.mmap[pos..pos + 4]);
+            let doc_id = u32::from_le_bytes(bytes);
+            pos += 4;
+
+            let mut parts = [0.0f32; 4];
+            for part in &mut parts {
+                bytes.copy_from_slice(&self.mmap[pos..pos + 4]);
+                *part = f32::from_le_bytes(bytes);
+                pos += 4;
+            }
+
+            postings.push((doc_id, parts));
+        }
+        Some(postings)
+    }
 }
 
 // Used during Querying to read from disk instantly
 pub struct MmapIndex {
     pub doc_map: Vec<String>,
+    pub sources: Vec<String>,
     pub cmd_names: Vec<String>,
     pub name_descs: Vec<String>,
-    pub inverted_dict: HashMap<String, (u64, u32)>, // word -> (byte_offset, num_postings)
+    pub field_lens: Vec<FieldLens>,
+    pub authority: Vec<f32>,
+    pub see_also: Vec<Vec<u32>>,
+    pub term_vectors: Vec<TermVector>,
+    pub examples: Vec<Vec<Example>>,
+    /// doc_id -> the tldr page with examples for it: its own for a tldr
+    /// page, the one of the same command for a section 1 or 8 man page.
+    pub example_pages: Vec<Option<u32>>,
+    /// The reverse of `example_pages`: tldr page -> the man pages showing
+    /// its examples.
+    pub example_targets: Vec<Vec<u32>>,
+    /// doc_id -> the page of the same command in the other format: the
+    /// section 1 or 8 man page of an info node, or a man page's info node.
+    pub info_links: Vec<Option<u32>>,
+    pub inverted_dict: HashMap<String, u32>, // word -> live documents containing it, over all segments
     pub cmd_name_index: HashMap<String, Vec<u32>>,
     pub desc_index: HashMap<String, Vec<u32>>,
-    mmap: memmap2::Mmap,
+    pub associations: Associations,
+    pub synonyms: SynonymTable,
+    #[cfg(feature = "embeddings")]
+    pub vectors: Option<VectorIndex>,
+    profile: RankingProfile,
+    doc_mults: Vec<f32>, // doc_id -> doc_type_multiplier under `profile`
+    segments: Vec<Segment>,
+    doc_locs: Vec<(u32, u32)>, // doc_id -> (segment, local doc id)
 }
 
 impl MmapIndex {
-    /// Reads a posting list directly from the memory-mapped file
-    pub fn get_postings(&self, word: &str) -> Option<Vec<(u32, f32)>> {
-        let &(offset, len) = self.inverted_dict.get(word)?;
-        let mut postings = Vec::with_capacity(len as usize);
-        let mut pos = offset as usize;
+    /// Doc id of the page with file name `fname`.
+    pub fn find_doc(&self, fname: &str) -> Option<u32> {
+        self.doc_map.iter().position(|f| f == fname).map(|i| i as u32)
+    }
 
-        for _ in 0..len {
-            let mut doc_bytes = [0u8; 4];
-            doc_bytes.copy_from_slice(&self.mmap[pos..pos + 4]);
-            let doc_id = u32::from_le_bytes(doc_bytes);
-            pos += 4;
+    pub fn profile(&self) -> &RankingProfile {
+        &self.profile
+    }
 
-            let mut score_bytes = [0u8; 4];
-            score_bytes.copy_from_slice(&self.mmap[pos..pos + 4]);
-            let score = f32::from_le_bytes(score_bytes);
-            pos += 4;
+    /// Query-independent weight of a page: section factor times authority.
+    pub fn doc_prior(&self, doc_id: u32) -> f32 {
+        self.doc_mults[doc_id as usize]
+    }
+
+    /// Switches the ranking parameters used by every subsequent search.
+    pub fn set_profile(&mut self, profile: RankingProfile) {
+        self.doc_mults = self
+            .doc_map
+            .iter()
+            .zip(&self.authority)
+            .map(|(fname, &authority)| {
+                doc_type_multiplier(fname, &profile) * authority.powf(profile.authority_weight)
+            })
+            .collect();
+        self.profile = profile;
+    }
+
+    /// Number of live documents containing `word`, i.e. the length of its posting list.
+    pub fn df(&self, word: &str) -> u32 {
+        self.inverted_dict.get(word).copied().unwrap_or(0)
+    }
+
+    /// Example commands to show with `doc_id`: a tldr page's own, or those
+    /// of the tldr page for a man page's command.
+    pub fn examples_for(&self, doc_id: u32) -> &[Example] {
+        match self.example_pages.get(doc_id as usize) {
+            Some(&Some(page)) => &self.examples[page as usize],
+            _ => &[],
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Documents in all segments, deleted and replaced ones included.
+    pub fn total_docs(&self) -> usize {
+        self.segments.iter().map(Segment::doc_count).sum()
+    }
 
-            postings.push((doc_id, score));
+    /// Stored page text for `doc_id`, borrowed straight from the mapped file.
+    pub fn doc_text(&self, doc_id: u32) -> &str {
+        let Some(&(segment, local_id)) = self.doc_locs.get(doc_id as usize) else {
+            return "";
+        };
+        self.segments[segment as usize].text(local_id)
+    }
+
+    /// The first segment's mapped file; vectors are only loaded when that
+    /// is the whole index.
+    #[cfg(feature = "embeddings")]
+    pub(crate) fn raw(&self) -> &[u8] {
+        &self.segments[0].mmap
+    }
+
+    /// Reads a posting list directly from the memory-mapped file, scored
+    /// with the current ranking profile
+    pub fn get_postings(&self, word: &str) -> Option<Vec<(u32, f32)>> {
+        let postings = self.get_field_postings(word)?;
+        Some(
+            postings
+                .into_iter()
+                .map(|(doc_id, parts)| {
+                    let mult = self.doc_mults[doc_id as usize];
+                    (doc_id, weighted(&parts, &self.profile) * mult)
+                })
+                .collect(),
+        )
+    }
+
+    /// Reads a posting list with its unweighted per-field BM25 parts from
+    /// every segment, skipping deleted documents. Each segment scored its
+    /// postings with its own document counts, so their IDF is replaced by
+    /// the one over the live documents of all segments, as `search` uses.
+    /// Length normalisation still uses each segment's own average field
+    /// lengths, which `merge` keeps.
+    pub fn get_field_postings(&self, word: &str) -> Option<Vec<(u32, FieldScores)>> {
+        let &df = self.inverted_dict.get(word)?;
+        let global_idf = idf(self.doc_map.len() as f32, df as f32);
+        let mut postings = Vec::with_capacity(df as usize);
+
+        for segment in &self.segments {
+            let Some(local) = segment.read_postings(word) else {
+                continue;
+            };
+            let own = idf(segment.doc_count() as f32, local.len() as f32);
+            let scale = if own > 0.0 { global_idf / own } else { 0.0 };
+            for (local_id, parts) in local {
+                if let Some(&Some(doc_id)) = segment.doc_ids.get(local_id as usize) {
+                    postings.push((doc_id, parts.map(|p| p * scale)));
+                }
+            }
         }
         Some(postings)
     }
 }
 
+/// BM25 inverse document frequency of a term found in `df` of `n` documents.
+pub(crate) fn idf(n: f32, df: f32) -> f32 {
+    ((n - df + 0.5) / (df + 0.5) + 1.0).ln().max(0.0)
+}
+
 #[inline]
 fn bm25_term(tf: f32, dl: f32, avgdl: f32, n: f32, df: f32) -> f32 {
     if tf == 0.0 || df == 0.0 || n == 0.0 {
         return 0.0;
     }
-    let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln().max(0.0);
     let ntf = (tf * (BM25_K1 + 1.0)) / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * dl / avgdl));
-    idf * ntf
+    idf(n, df) * ntf
 }
 
 pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
@@ -77,14 +306,23 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
     let mut reader = BufReader::new(file);
 
     let mut doc_map = Vec::with_capacity(*total_docs as usize);
+    let mut sources = Vec::with_capacity(*total_docs as usize);
     let mut cmd_names = Vec::with_capacity(*total_docs as usize);
     let mut name_descs = Vec::with_capacity(*total_docs as usize);
-    let mut inverted: HashMap<String, Vec<(u32, f32)>> = HashMap::new();
+    let mut field_lens = Vec::with_capacity(*total_docs as usize);
+    let mut texts = Vec::with_capacity(*total_docs as usize);
+    let mut inverted: HashMap<String, Vec<(u32, FieldScores)>> = HashMap::new();
+    let default_profile = RankingProfile::default();
     let mut cmd_name_index: HashMap<String, Vec<u32>> = HashMap::new();
     let mut desc_index: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut field_terms: Vec<Vec<String>> = Vec::with_capacity(*total_docs as usize);
+    let mut see_also_refs: Vec<Vec<String>> = Vec::with_capacity(*total_docs as usize);
+    let mut mention_refs: Vec<Vec<String>> = Vec::with_capacity(*total_docs as usize);
+    let mut examples: Vec<Vec<Example>> = Vec::with_capacity(*total_docs as usize);
 
     for doc_id in 0..*total_docs {
         let fname = read_str(&mut reader)?;
+        let source = read_str(&mut reader)?;
         let cmd_name = read_str(&mut reader)?;
         let desc_len = read_u32(&mut reader)? as f32;
         let synopsis_len = read_u32(&mut reader)? as f32;
@@ -93,12 +331,17 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
         let synopsis_tf = read_tf_map(&mut reader)?;
         let body_tf = read_tf_map(&mut reader)?;
         let name_desc_raw = read_str(&mut reader)?;
-
-        let type_mult = doc_type_multiplier(&fname);
+        let text = read_str(&mut reader)?;
+        see_also_refs.push(read_str_list(&mut reader)?);
+        mention_refs.push(read_str_list(&mut reader)?);
+        examples.push(read_examples(&mut reader)?);
 
         doc_map.push(fname);
+        sources.push(source);
         cmd_names.push(cmd_name.clone());
         name_descs.push(name_desc_raw);
+        field_lens.push([desc_len as u32, synopsis_len as u32, body_len as u32]);
+        texts.push(text);
 
         if !cmd_name.is_empty() {
             cmd_name_index
@@ -111,6 +354,20 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
             desc_index.entry(term.clone()).or_default().push(doc_id);
         }
 
+        // Terms for association mining and embeddings, most telling first:
+        // command name, description, then synopsis by frequency
+        let mut terms: Vec<String> = Vec::new();
+        if !cmd_name.is_empty() {
+            terms.push(cmd_name.clone());
+        }
+        let mut desc_terms: Vec<&String> = desc_tf.keys().collect();
+        desc_terms.sort();
+        terms.extend(desc_terms.into_iter().cloned());
+        let mut syn_terms: Vec<(&String, &u32)> = synopsis_tf.iter().collect();
+        syn_terms.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        terms.extend(syn_terms.into_iter().map(|(t, _)| t.clone()));
+        field_terms.push(terms);
+
         let all_terms: HashSet<String> = desc_tf
             .keys()
             .chain(synopsis_tf.keys())
@@ -123,7 +380,7 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
             let df = *global_df.get(term).unwrap_or(&1) as f32;
 
             let cmd_score = if term == &cmd_name && !cmd_name.is_empty() {
-                bm25_term(1.0, 1.0, 1.0, n, df) * WEIGHT_CMD_NAME
+                bm25_term(1.0, 1.0, 1.0, n, df)
             } else {
                 0.0
             };
@@ -135,7 +392,7 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
                     avg_desc_len.max(1.0),
                     n,
                     df,
-                ) * WEIGHT_NAME_DESC
+                )
             } else {
                 0.0
             };
@@ -147,7 +404,7 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
                     avg_synopsis_len.max(1.0),
                     n,
                     df,
-                ) * WEIGHT_SYNOPSIS
+                )
             } else {
                 0.0
             };
@@ -159,58 +416,211 @@ pub fn build_index(temp_path: &str, stats: &CrawlStats) -> io::Result<Index> {
                     avg_body_len.max(1.0),
                     n,
                     df,
-                ) * WEIGHT_BODY
+                )
             } else {
                 0.0
             };
 
-            let score = (cmd_score + desc_score + syn_score + body_score) * type_mult;
-            if score > 0.0 {
+            let parts = [cmd_score, desc_score, syn_score, body_score];
+            if parts.iter().any(|&p| p > 0.0) {
                 inverted
                     .entry(term.clone())
                     .or_default()
-                    .push((doc_id, score));
+                    .push((doc_id, parts));
             }
         }
     }
 
-    for postings in inverted.values_mut() {
-        postings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-    }
+    sort_postings(&mut inverted, &doc_map);
+
+    let associations = mine_associations(&field_terms);
+
+    let resolver = PageResolver::new(&doc_map);
+    let see_also: Vec<Vec<u32>> = see_also_refs
+        .iter()
+        .enumerate()
+        .map(|(doc_id, targets)| resolver.resolve_all(doc_id as u32, targets))
+        .collect();
+    let links: Vec<Vec<u32>> = see_also
+        .iter()
+        .zip(&mention_refs)
+        .enumerate()
+        .map(|(doc_id, (see_also, mentions))| {
+            let mut out = see_also.clone();
+            for id in resolver.resolve_all(doc_id as u32, mentions) {
+                if !out.contains(&id) {
+                    out.push(id);
+                }
+            }
+            out
+        })
+        .collect();
+    let authority = pagerank(&links);
+    let term_vectors = build_term_vectors(&inverted, doc_map.len(), &default_profile);
 
     Ok(Index {
         doc_map,
+        sources,
         cmd_names,
         name_descs,
+        field_lens,
+        texts,
         inverted,
         cmd_name_index,
         desc_index,
+        field_terms,
+        associations,
+        links,
+        authority,
+        see_also,
+        term_vectors,
+        examples,
+        vectors: None,
     })
 }
 
+/// Orders every posting list best first under the default profile, as
+/// `save_index` writes them.
+pub(crate) fn sort_postings(
+    inverted: &mut HashMap<String, Vec<(u32, FieldScores)>>,
+    doc_map: &[String],
+) {
+    let profile = RankingProfile::default();
+    let type_mults: Vec<f32> = doc_map
+        .iter()
+        .map(|fname| doc_type_multiplier(fname, &profile))
+        .collect();
+    for postings in inverted.values_mut() {
+        postings.sort_by_cached_key(|&(doc_id, parts)| {
+            std::cmp::Reverse((weighted(&parts, &profile) * type_mults[doc_id as usize]).to_bits())
+        });
+    }
+}
+
+/// Keeps each document's `RELATED_TERMS_PER_DOC` highest-scoring terms
+/// (field-weighted BM25 under `profile`), normalised to unit length. The
+/// command-name part is left out: it would make every vector point mostly
+/// at the page's own name.
+pub(crate) fn build_term_vectors(
+    inverted: &HashMap<String, Vec<(u32, FieldScores)>>,
+    doc_count: usize,
+    profile: &RankingProfile,
+) -> Vec<TermVector> {
+    let mut vectors: Vec<TermVector> = vec![Vec::new(); doc_count];
+    for (term, postings) in inverted {
+        for (doc_id, parts) in postings {
+            let content = weighted(&[0.0, parts[1], parts[2], parts[3]], profile);
+            if content > 0.0 {
+                vectors[*doc_id as usize].push((term.clone(), content));
+            }
+        }
+    }
+    for vector in &mut vectors {
+        vector.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        vector.truncate(RELATED_TERMS_PER_DOC);
+        let norm = vector.iter().map(|(_, w)| w * w).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|(_, w)| *w /= norm);
+        }
+        vector.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    vectors
+}
+
 pub fn save_index(path: &str, index: &Index) -> io::Result<()> {
     let mut w = BufWriter::new(File::create(path)?);
 
+    // 0. Write the header
+    w.write_all(INDEX_MAGIC)?;
+    write_u32(&mut w, INDEX_VERSION)?;
+
     // 1. Write docs metadata
     write_u32(&mut w, index.doc_map.len() as u32)?;
     for i in 0..index.doc_map.len() {
         write_str(&mut w, &index.doc_map[i])?;
+        write_str(&mut w, &index.sources[i])?;
         write_str(&mut w, &index.cmd_names[i])?;
         write_str(&mut w, &index.name_descs[i])?;
+        for &len in &index.field_lens[i] {
+            write_u32(&mut w, len)?;
+        }
+        write_f32(&mut w, index.authority[i])?;
+        write_u32(&mut w, index.see_also[i].len() as u32)?;
+        for &target in &index.see_also[i] {
+            write_u32(&mut w, target)?;
+        }
+        write_u32(&mut w, index.term_vectors[i].len() as u32)?;
+        for (term, weight) in &index.term_vectors[i] {
+            write_str(&mut w, term)?;
+            write_f32(&mut w, *weight)?;
+        }
+        write_examples(&mut w, &index.examples[i])?;
     }
 
     // 2. Write Postings dynamically and track offsets
     let mut dict = Vec::with_capacity(index.inverted.len());
     for (word, postings) in &index.inverted {
         let offset = w.stream_position()?;
-        for &(doc_id, score) in postings {
-            write_u32(&mut w, doc_id)?;
-            write_f32(&mut w, score)?;
+        for (doc_id, parts) in postings {
+            write_u32(&mut w, *doc_id)?;
+            for &part in parts {
+                write_f32(&mut w, part)?;
+            }
         }
         dict.push((word.clone(), offset, postings.len() as u32));
     }
 
-    // 3. Write Dictionary
+    // 3. Write page texts, then a table of (offset, len) per doc
+    let mut text_table = Vec::with_capacity(index.texts.len());
+    for text in &index.texts {
+        text_table.push((w.stream_position()?, text.len() as u32));
+        w.write_all(text.as_bytes())?;
+    }
+    let text_table_offset = w.stream_position()?;
+    for (offset, len) in text_table {
+        w.write_all(&offset.to_le_bytes())?;
+        write_u32(&mut w, len)?;
+    }
+
+    // 4. Write term associations
+    let assoc_offset = w.stream_position()?;
+    let mut assoc: Vec<(&String, &Vec<(String, f32)>)> = index.associations.iter().collect();
+    assoc.sort_by(|a, b| a.0.cmp(b.0));
+    write_u32(&mut w, assoc.len() as u32)?;
+    for (term, related) in assoc {
+        write_str(&mut w, term)?;
+        write_u32(&mut w, related.len() as u32)?;
+        for (r, weight) in related {
+            write_str(&mut w, r)?;
+            write_f32(&mut w, *weight)?;
+        }
+    }
+
+    // 5. Write dense vectors (dim 0 when built without an embedding model)
+    let vectors_offset = w.stream_position()?;
+    match &index.vectors {
+        Some(v) => {
+            write_u32(&mut w, v.dim as u32)?;
+            write_u32(&mut w, index.doc_map.len() as u32)?;
+            for &x in &v.docs {
+                write_f32(&mut w, x)?;
+            }
+            write_u32(&mut w, v.terms.len() as u32)?;
+            for (term, vec) in &v.terms {
+                write_str(&mut w, term)?;
+                for &x in vec {
+                    write_f32(&mut w, x)?;
+                }
+            }
+        }
+        None => write_u32(&mut w, 0)?,
+    }
+
+    // 6. Write Dictionary
     let dict_offset = w.stream_position()?;
     write_u32(&mut w, dict.len() as u32)?;
     for (word, offset, len) in dict {
@@ -219,55 +629,169 @@ pub fn save_index(path: &str, index: &Index) -> io::Result<()> {
         write_u32(&mut w, len)?;
     }
 
-    // 4. Write Footer (8 bytes pointing to the dictionary)
+    // 7. Write Footer (32 bytes: the offsets of the vectors, the associations,
+    //    the text table and the dictionary, in that order)
+    w.write_all(&vectors_offset.to_le_bytes())?;
+    w.write_all(&assoc_offset.to_le_bytes())?;
+    w.write_all(&text_table_offset.to_le_bytes())?;
     w.write_all(&dict_offset.to_le_bytes())?;
 
     w.flush()
 }
 
-pub fn load_index(path: &str) -> io::Result<MmapIndex> {
+/// Section offsets stored in the last 32 bytes of an index file.
+pub(crate) struct Footer {
+    pub vectors: usize,
+    pub assoc: usize,
+    pub text_table: usize,
+    pub dict: usize,
+}
+
+/// Reads the footer of `file`, which must be at least 32 bytes long.
+pub(crate) fn read_footer(file: &[u8]) -> Footer {
+    let len = file.len();
+    let at = |start: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&file[start..start + 8]);
+        u64::from_le_bytes(bytes) as usize
+    };
+    Footer {
+        vectors: at(len - 32),
+        assoc: at(len - 24),
+        text_table: at(len - 16),
+        dict: at(len - 8),
+    }
+}
+
+/// Everything one segment file holds, with segment-local doc ids.
+struct SegmentData {
+    segment: Segment,
+    sources: Vec<String>,
+    cmd_names: Vec<String>,
+    name_descs: Vec<String>,
+    field_lens: Vec<FieldLens>,
+    authority: Vec<f32>,
+    see_also: Vec<Vec<u32>>,
+    term_vectors: Vec<TermVector>,
+    examples: Vec<Vec<Example>>,
+    associations: Associations,
+    #[cfg(feature = "embeddings")]
+    vectors: Option<VectorIndex>,
+}
+
+/// True if `path` starts like a file written by `save_index`, as opposed
+/// to a segment list.
+pub fn is_segment_file(path: &str) -> io::Result<bool> {
+    let mut magic = [0u8; 4];
+    match File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == INDEX_MAGIC),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn open_segment(path: &str) -> io::Result<SegmentData> {
     let file = File::open(path)?;
     let mmap = unsafe { MmapOptions::new().map(&file)? };
 
     let len = mmap.len();
-    if len < 8 {
+    if len < 40 {
         return Err(io::Error::new(io::ErrorKind::InvalidData, "File too small"));
     }
+    if &mmap[..4] != INDEX_MAGIC || mmap[4..8] != INDEX_VERSION.to_le_bytes() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unknown index format (written by an older version? re-run `index`)",
+        ));
+    }
 
-    // Read the footer to find the dictionary
-    let mut footer = [0u8; 8];
-    footer.copy_from_slice(&mmap[len - 8..]);
-    let dict_offset = u64::from_le_bytes(footer) as usize;
+    let Footer {
+        vectors: vectors_offset,
+        assoc: assoc_offset,
+        text_table: text_table_offset,
+        dict: dict_offset,
+    } = read_footer(&mmap);
+    if text_table_offset > assoc_offset
+        || assoc_offset > vectors_offset
+        || vectors_offset > dict_offset
+        || dict_offset > len - 32
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Bad footer (index written by an older version? re-run `index`)",
+        ));
+    }
 
     // 1. Read metadata from the start
     let mut r = Cursor::new(&mmap[..dict_offset]);
+    r.set_position(8);
     let doc_count = read_u32(&mut r)? as usize;
 
-    let mut doc_map = Vec::with_capacity(doc_count);
+    let mut fnames = Vec::with_capacity(doc_count);
+    let mut sources = Vec::with_capacity(doc_count);
     let mut cmd_names = Vec::with_capacity(doc_count);
     let mut name_descs = Vec::with_capacity(doc_count);
-    let mut cmd_name_index: HashMap<String, Vec<u32>> = HashMap::new();
+    let mut field_lens = Vec::with_capacity(doc_count);
+    let mut authority = Vec::with_capacity(doc_count);
+    let mut see_also = Vec::with_capacity(doc_count);
+    let mut term_vectors = Vec::with_capacity(doc_count);
+    let mut examples = Vec::with_capacity(doc_count);
 
-    for doc_id in 0..doc_count {
-        let fname = read_str(&mut r)?;
-        let cmd_name = read_str(&mut r)?;
-        let name_desc = read_str(&mut r)?;
+    for _ in 0..doc_count {
+        fnames.push(read_str(&mut r)?);
+        sources.push(read_str(&mut r)?);
+        cmd_names.push(read_str(&mut r)?);
+        name_descs.push(read_str(&mut r)?);
+        field_lens.push([read_u32(&mut r)?, read_u32(&mut r)?, read_u32(&mut r)?]);
+        authority.push(read_f32(&mut r)?);
+        let n_see_also = read_u32(&mut r)? as usize;
+        let mut targets = Vec::with_capacity(n_see_also);
+        for _ in 0..n_see_also {
+            targets.push(read_u32(&mut r)?);
+        }
+        see_also.push(targets);
+        let n_terms = read_u32(&mut r)? as usize;
+        let mut vector = Vec::with_capacity(n_terms);
+        for _ in 0..n_terms {
+            let term = read_str(&mut r)?;
+            vector.push((term, read_f32(&mut r)?));
+        }
+        term_vectors.push(vector);
+        examples.push(read_examples(&mut r)?);
+    }
 
-        if !cmd_name.is_empty() {
-            cmd_name_index
-                .entry(cmd_name.clone())
-                .or_default()
-                .push(doc_id as u32);
+    // 2. Read the text table
+    let mut r_text = Cursor::new(&mmap[text_table_offset..assoc_offset]);
+    let mut text_table = Vec::with_capacity(doc_count);
+    for _ in 0..doc_count {
+        let mut off_buf = [0u8; 8];
+        r_text.read_exact(&mut off_buf)?;
+        text_table.push((u64::from_le_bytes(off_buf), read_u32(&mut r_text)?));
+    }
+
+    // 3. Read the term associations
+    let mut r_assoc = Cursor::new(&mmap[assoc_offset..vectors_offset]);
+    let assoc_len = read_u32(&mut r_assoc)? as usize;
+    let mut associations: Associations = HashMap::with_capacity(assoc_len);
+    for _ in 0..assoc_len {
+        let term = read_str(&mut r_assoc)?;
+        let k = read_u32(&mut r_assoc)? as usize;
+        let mut related = Vec::with_capacity(k);
+        for _ in 0..k {
+            let r = read_str(&mut r_assoc)?;
+            related.push((r, read_f32(&mut r_assoc)?));
         }
-        doc_map.push(fname);
-        cmd_names.push(cmd_name);
-        name_descs.push(name_desc);
+        associations.insert(term, related);
     }
 
-    // 2. Read the dictionary into memory
-    let mut r_dict = Cursor::new(&mmap[dict_offset..len - 8]);
+    // 4. Locate the dense vectors (read lazily from the map)
+    #[cfg(feature = "embeddings")]
+    let vectors = VectorIndex::open(&mmap[vectors_offset..dict_offset], vectors_offset, doc_count)?;
+
+    // 5. Read the dictionary into memory
+    let mut r_dict = Cursor::new(&mmap[dict_offset..len - 32]);
     let dict_len = read_u32(&mut r_dict)?;
-    let mut inverted_dict = HashMap::with_capacity(dict_len as usize);
+    let mut dict = HashMap::with_capacity(dict_len as usize);
 
     for _ in 0..dict_len {
         let word = read_str(&mut r_dict)?;
@@ -276,10 +800,220 @@ pub fn load_index(path: &str) -> io::Result<MmapIndex> {
         let offset = u64::from_le_bytes(off_buf);
         let num_postings = read_u32(&mut r_dict)?;
 
-        inverted_dict.insert(word, (offset, num_postings));
+        dict.insert(word, (offset, num_postings));
+    }
+
+    Ok(SegmentData {
+        segment: Segment {
+            path: path.to_string(),
+            fnames,
+            doc_ids: Vec::new(),
+            dict,
+            text_table,
+            mmap,
+        },
+        sources,
+        cmd_names,
+        name_descs,
+        field_lens,
+        authority,
+        see_also,
+        term_vectors,
+        examples,
+        associations,
+        #[cfg(feature = "embeddings")]
+        vectors,
+    })
+}
+
+/// Links every tldr page to itself and to the section 1 and 8 man pages of
+/// the same command name, which may come from other segments.
+fn link_examples(
+    doc_map: &[String],
+    cmd_names: &[String],
+    cmd_name_index: &HashMap<String, Vec<u32>>,
+    examples: &[Vec<Example>],
+) -> Vec<Option<u32>> {
+    let mut example_pages = vec![None; doc_map.len()];
+    for (doc_id, page_examples) in examples.iter().enumerate() {
+        if page_examples.is_empty() {
+            continue;
+        }
+        example_pages[doc_id] = Some(doc_id as u32);
+        for &target in cmd_name_index.get(&cmd_names[doc_id]).into_iter().flatten() {
+            let is_command = split_page(&doc_map[target as usize])
+                .is_some_and(|(_, section)| section.starts_with(['1', '8']));
+            if is_command && example_pages[target as usize].is_none() {
+                example_pages[target as usize] = Some(doc_id as u32);
+            }
+        }
+    }
+    example_pages
+}
+
+/// Links every info node that documents a command to the section 1 and 8
+/// man pages of that command name, and those pages back to the first such
+/// node.
+fn link_info_nodes(
+    doc_map: &[String],
+    cmd_names: &[String],
+    cmd_name_index: &HashMap<String, Vec<u32>>,
+) -> Vec<Option<u32>> {
+    let mut info_links = vec![None; doc_map.len()];
+    for (doc_id, fname) in doc_map.iter().enumerate() {
+        if !is_info_page(fname) || cmd_names[doc_id].is_empty() {
+            continue;
+        }
+        for &target in cmd_name_index.get(&cmd_names[doc_id]).into_iter().flatten() {
+            let is_command = split_page(&doc_map[target as usize])
+                .is_some_and(|(_, section)| section.starts_with(['1', '8']));
+            if !is_command {
+                continue;
+            }
+            info_links[doc_id].get_or_insert(target);
+            info_links[target as usize].get_or_insert(doc_id as u32);
+        }
     }
+    info_links
+}
 
-    // 3. Rebuild desc_index
+/// Loads an index file, or every segment named by a segment list (see
+/// `crate::segment`) as one index. A page in a later segment replaces the
+/// page of the same name in earlier ones.
+pub fn load_index(path: &str) -> io::Result<MmapIndex> {
+    let specs = if is_segment_file(path)? {
+        vec![(path.to_string(), HashSet::new())]
+    } else {
+        SegmentList::read(path)?.resolve(path)
+    };
+    if specs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("'{path}' lists no segments"),
+        ));
+    }
+    let mut parts = Vec::with_capacity(specs.len());
+    for (segment_path, deleted) in specs {
+        let data = open_segment(&segment_path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{segment_path}: {e}")))?;
+        parts.push((data, deleted));
+    }
+
+    // 1. Decide which documents are live, newest segment first. Pages of
+    // one segment may share a name when they come from different sources.
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut live: Vec<Vec<bool>> = vec![Vec::new(); parts.len()];
+    for (i, (data, deleted)) in parts.iter().enumerate().rev() {
+        let fnames = &data.segment.fnames;
+        live[i] = fnames
+            .iter()
+            .map(|fname| !deleted.contains(fname) && !seen.contains(fname))
+            .collect();
+        seen.extend(fnames.iter().cloned());
+    }
+
+    // 2. Number the live documents and gather their metadata
+    let mut doc_map = Vec::new();
+    let mut sources = Vec::new();
+    let mut cmd_names = Vec::new();
+    let mut name_descs = Vec::new();
+    let mut field_lens = Vec::new();
+    let mut authority = Vec::new();
+    let mut term_vectors = Vec::new();
+    let mut examples = Vec::new();
+    let mut doc_locs = Vec::new();
+    for (seg, ((data, _), live)) in parts.iter_mut().zip(&live).enumerate() {
+        let mut doc_ids = Vec::with_capacity(live.len());
+        for (local, &is_live) in live.iter().enumerate() {
+            if !is_live {
+                doc_ids.push(None);
+                continue;
+            }
+            doc_ids.push(Some(doc_map.len() as u32));
+            doc_locs.push((seg as u32, local as u32));
+            doc_map.push(data.segment.fnames[local].clone());
+            sources.push(std::mem::take(&mut data.sources[local]));
+            cmd_names.push(std::mem::take(&mut data.cmd_names[local]));
+            name_descs.push(std::mem::take(&mut data.name_descs[local]));
+            field_lens.push(data.field_lens[local]);
+            authority.push(data.authority[local]);
+            term_vectors.push(std::mem::take(&mut data.term_vectors[local]));
+            examples.push(std::mem::take(&mut data.examples[local]));
+        }
+        data.segment.doc_ids = doc_ids;
+    }
+    let see_also: Vec<Vec<u32>> = doc_locs
+        .iter()
+        .map(|&(seg, local)| {
+            let (data, _) = &parts[seg as usize];
+            data.see_also[local as usize]
+                .iter()
+                .filter_map(|&t| data.segment.doc_ids.get(t as usize).copied().flatten())
+                .collect()
+        })
+        .collect();
+
+    let mut cmd_name_index: HashMap<String, Vec<u32>> = HashMap::new();
+    for (doc_id, cmd_name) in cmd_names.iter().enumerate() {
+        if !cmd_name.is_empty() {
+            cmd_name_index
+                .entry(cmd_name.clone())
+                .or_default()
+                .push(doc_id as u32);
+        }
+    }
+    let example_pages = link_examples(&doc_map, &cmd_names, &cmd_name_index, &examples);
+    let mut example_targets: Vec<Vec<u32>> = vec![Vec::new(); doc_map.len()];
+    for (doc_id, &page) in example_pages.iter().enumerate() {
+        if let Some(page) = page.filter(|&p| p as usize != doc_id) {
+            example_targets[page as usize].push(doc_id as u32);
+        }
+    }
+    let info_links = link_info_nodes(&doc_map, &cmd_names, &cmd_name_index);
+
+    // 3. Document frequencies over the live pages of all segments. Only
+    //    segments with deleted or replaced pages need their postings read.
+    let mut inverted_dict: HashMap<String, u32> = HashMap::new();
+    for (data, _) in &parts {
+        let segment = &data.segment;
+        let all_live = segment.live_docs() == segment.doc_count();
+        for (word, &(_, len)) in &segment.dict {
+            let live = if all_live {
+                len
+            } else {
+                segment
+                    .read_postings(word)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|(local_id, _)| {
+                        matches!(segment.doc_ids.get(*local_id as usize), Some(Some(_)))
+                    })
+                    .count() as u32
+            };
+            if live > 0 {
+                *inverted_dict.entry(word.clone()).or_insert(0) += live;
+            }
+        }
+    }
+
+    // 4. Associations from the largest segment that has the term
+    let mut by_size: Vec<usize> = (0..parts.len()).collect();
+    by_size.sort_by_key(|&i| std::cmp::Reverse(parts[i].0.segment.doc_count()));
+    let mut associations: Associations = HashMap::new();
+    for i in by_size {
+        for (term, related) in std::mem::take(&mut parts[i].0.associations) {
+            associations.entry(term).or_insert(related);
+        }
+    }
+
+    // 5. Vectors hold segment-local doc ids, so only one whole segment can use them
+    #[cfg(feature = "embeddings")]
+    let vectors = match &mut parts[..] {
+        [(data, _)] if data.segment.live_docs() == data.segment.doc_count() => data.vectors.take(),
+        _ => None,
+    };
+
+    // 6. Rebuild desc_index
     use crate::text::make_stemmer;
     use crate::text::tokenize;
     let stemmer = make_stemmer();
@@ -290,13 +1024,37 @@ pub fn load_index(path: &str) -> io::Result<MmapIndex> {
         }
     }
 
-    Ok(MmapIndex {
+    // 7. Load query synonyms from next to the index
+    let synonyms = SynonymTable::load(&Path::new(path).with_file_name(SYNONYMS_PATH), &stemmer);
+
+    // 8. Load the ranking profile
+    let profile = RankingProfile::load(&Path::new(path).with_file_name(RANKING_PROFILE_PATH));
+
+    let mut index = MmapIndex {
         doc_map,
+        sources,
         cmd_names,
         name_descs,
+        field_lens,
+        authority,
+        see_also,
+        term_vectors,
+        examples,
+        example_pages,
+        example_targets,
+        info_links,
         inverted_dict,
         cmd_name_index,
         desc_index,
-        mmap,
-    })
+        associations,
+        synonyms,
+        #[cfg(feature = "embeddings")]
+        vectors,
+        profile: RankingProfile::default(),
+        doc_mults: Vec::new(),
+        segments: parts.into_iter().map(|(data, _)| data.segment).collect(),
+        doc_locs,
+    };
+    index.set_profile(profile);
+    Ok(index)
 }
@@ -0,0 +1,422 @@
+//! GNU info manuals (`/usr/share/info`): Texinfo output split into nodes
+//! by `^_` separators, each node indexed as `<manual>-<node>.info` and
+//! linked to the man page of the command the manual's dir entries name.
+//!
+//! ```text
+//! INFO-DIR-SECTION Basic
+//! START-INFO-DIR-ENTRY
+//! * cp: (coreutils)cp invocation.     Copy files.
+//! END-INFO-DIR-ENTRY
+//! ^_
+//! File: coreutils.info,  Node: cp invocation,  Next: dd invocation,  Up: Basic operations
+//!
+//! 11.1 ‘cp’: Copy files and directories
+//! =====================================
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use rust_stemmers::Stemmer;
+
+use crate::constants::{INFO_FNAME_MAX_BYTES, INFO_SECTION};
+use crate::doc::{normalise_text, DocFields};
+use crate::graph::{extract_refs, split_page};
+use crate::text::{count_into, tokenize};
+
+const NODE_SEPARATOR: char = '\x1f';
+/// Starts the list of subnodes at the end of a node.
+const MENU_START: &str = "* Menu:";
+/// The manual's front page: a preface and the table of contents.
+const TOP_NODE: &str = "Top";
+
+/// True for files of an info manual: `grep.info.gz`, the parts of a split
+/// manual (`find.info-1.gz`) and the `dir` menu. Only whole manuals
+/// produce documents; see `parse_info_docs`.
+pub fn is_info_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    name == "dir"
+        || name.ends_with(".info")
+        || name
+            .rsplit_once(".info-")
+            .is_some_and(|(_, part)| part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// True if `fname` names an info node.
+pub fn is_info_page(fname: &str) -> bool {
+    split_page(fname).is_some_and(|(_, section)| section == INFO_SECTION)
+}
+
+fn read_info(path: &Path) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(File::open(path)?).read_to_end(&mut bytes)?;
+    } else {
+        File::open(path)?.read_to_end(&mut bytes)?;
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The files listed in the `Indirect:` table of a split manual, next to
+/// `path` and compressed like it.
+fn indirect_parts(path: &Path, content: &str) -> Vec<PathBuf> {
+    let Some(table) = content
+        .split(NODE_SEPARATOR)
+        .find_map(|c| c.trim_start().strip_prefix("Indirect:"))
+    else {
+        return Vec::new();
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    table
+        .lines()
+        .filter_map(|line| line.rsplit_once(": ").map(|(name, _)| name.trim()))
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let plain = dir.join(name);
+            let gz = dir.join(format!("{name}.gz"));
+            if plain.exists() {
+                plain
+            } else {
+                gz
+            }
+        })
+        .collect()
+}
+
+/// Commands of the manual's dir entries by the node describing them, e.g.
+/// `cp invocation` -> `cp`. An entry without a node names the `Top` node;
+/// entries of several words (`Common options`) are not commands.
+fn dir_entries(content: &str) -> HashMap<String, String> {
+    let mut commands = HashMap::new();
+    let mut in_entry = false;
+    for line in content.lines() {
+        match line.trim() {
+            "START-INFO-DIR-ENTRY" => in_entry = true,
+            "END-INFO-DIR-ENTRY" => in_entry = false,
+            line if in_entry => {
+                let Some((name, target)) = line
+                    .strip_prefix("* ")
+                    .and_then(|entry| entry.split_once(": ("))
+                else {
+                    continue;
+                };
+                let Some((_, node)) = target.split_once(')') else {
+                    continue;
+                };
+                // The node ends at the first period followed by a space
+                let node = node
+                    .find(". ")
+                    .map_or(node.trim_end().trim_end_matches('.'), |end| &node[..end]);
+                let node = if node.is_empty() { "Top" } else { node };
+                if !name.contains(char::is_whitespace) {
+                    commands
+                        .entry(node.to_string())
+                        .or_insert_with(|| name.to_lowercase());
+                }
+            }
+            _ => {}
+        }
+    }
+    commands
+}
+
+/// `File: coreutils.info,  Node: cp invocation,  Next: dd invocation, ...`
+/// -> the manual and node names, and the next node's if any.
+fn parse_header(line: &str) -> Option<(&str, &str, Option<&str>)> {
+    let mut manual = None;
+    let mut node = None;
+    let mut next = None;
+    for field in line.split(",  ") {
+        match field.trim().split_once(": ") {
+            Some(("File", file)) => manual = Some(file.trim_end_matches(".info")),
+            Some(("Node", name)) => node = Some(name.trim()),
+            Some(("Next", name)) => next = Some(name.trim()),
+            _ => {}
+        }
+    }
+    Some((manual?, node?, next))
+}
+
+/// `11.1 ‘cp’: Copy files and directories` -> `cp: Copy files and directories`.
+/// Older manuals quote as `` `cp' ``.
+fn plain_title(title: &str) -> String {
+    let title = title.trim();
+    let title = match title.split_once(' ') {
+        Some((number, rest)) if number.chars().all(|c| c.is_ascii_digit() || c == '.') => rest,
+        _ => title,
+    };
+    let title = title.replace(['‘', '’'], "");
+    if title.contains('`') {
+        title.replace(['`', '\''], "")
+    } else {
+        title
+    }
+}
+
+/// `cp invocation` -> `cp-invocation`
+fn slug(node: &str) -> String {
+    let mut slug = String::with_capacity(node.len());
+    for c in node.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// `<manual>-<node>[-<n>].info`, the node's part cut short to fit in
+/// `INFO_FNAME_MAX_BYTES`.
+fn info_fname(manual: &str, node: &str, n: Option<usize>) -> String {
+    let suffix = match n {
+        Some(n) => format!("-{n}.{INFO_SECTION}"),
+        None => format!(".{INFO_SECTION}"),
+    };
+    let stem = format!("{}-{}", slug(manual), slug(node));
+    let max = INFO_FNAME_MAX_BYTES.saturating_sub(suffix.len());
+    let end = stem
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .take_while(|&end| end <= max)
+        .last()
+        .unwrap_or(0);
+    format!("{}{suffix}", stem[..end].trim_end_matches('-'))
+}
+
+/// One node as `DocFields`. The underlined title is the NAME description,
+/// indented lines starting with the command are the synopsis, and the rest
+/// up to the menu is the body; `None` for a node that is only a menu.
+fn parse_node(
+    manual: &str,
+    node: &str,
+    body: &str,
+    command: Option<&str>,
+    stemmer: &Stemmer,
+) -> Option<DocFields> {
+    let lines: Vec<&str> = body.lines().collect();
+    let title = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .filter(|&i| {
+            lines.get(i + 1).is_some_and(|under| {
+                let under = under.trim();
+                !under.is_empty() && under.chars().all(|c| matches!(c, '=' | '*' | '-' | '.'))
+            })
+        });
+
+    let name_desc_raw = title.map_or_else(|| node.to_string(), |i| plain_title(lines[i]));
+    let mut name_desc_tf = HashMap::new();
+    let mut synopsis_tf = HashMap::new();
+    let mut body_tf = HashMap::new();
+    let name_desc_len = count_into(tokenize(&name_desc_raw, stemmer), &mut name_desc_tf);
+    let mut synopsis_len = 0;
+    let mut body_len = 0;
+    let mut text = String::with_capacity(body.len());
+    let mut in_menu = false;
+    for (i, line) in lines.iter().enumerate() {
+        if title.is_some_and(|t| i == t + 1) {
+            continue;
+        }
+        text += line;
+        text.push('\n');
+        // Menu entries only repeat the titles of other nodes
+        in_menu |= line.starts_with(MENU_START);
+        if title == Some(i) || in_menu {
+            continue;
+        }
+        let is_synopsis = line.starts_with("     ")
+            && command.is_some_and(|cmd| line.split_whitespace().next() == Some(cmd));
+        if is_synopsis {
+            synopsis_len += count_into(tokenize(line, stemmer), &mut synopsis_tf);
+        } else {
+            body_len += count_into(tokenize(line, stemmer), &mut body_tf);
+        }
+    }
+    if synopsis_len + body_len == 0 {
+        return None;
+    }
+
+    Some(DocFields {
+        fname: info_fname(manual, node, None),
+        cmd_name: command.map_or_else(String::new, |cmd| stemmer.stem(cmd).into_owned()),
+        name_desc_raw,
+        name_desc_tf,
+        name_desc_len,
+        synopsis_tf,
+        synopsis_len,
+        body_tf,
+        body_len,
+        text: normalise_text(&text),
+        refs: extract_refs(&text),
+        examples: Vec::new(),
+    })
+}
+
+/// Parses the info manual at `path` into one `DocFields` per node, reading
+/// the parts of a split manual along with it. Part files on their own and
+/// the `dir` menu give none. The `Top` node is left out, and a dir entry
+/// naming it links the node after it instead. Nodes whose names make the
+/// same file name, once cut to length, get a numeric suffix.
+pub fn parse_info_docs(path: &Path, stemmer: &Stemmer) -> Vec<DocFields> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    if !name.ends_with(".info") {
+        return Vec::new();
+    }
+    let Ok(mut content) = read_info(path) else {
+        return Vec::new();
+    };
+    for part in indirect_parts(path, &content) {
+        if let Ok(part) = read_info(&part) {
+            content.push(NODE_SEPARATOR);
+            content += &part;
+        }
+    }
+
+    let nodes: Vec<(&str, &str, Option<&str>, &str)> = content
+        .split(NODE_SEPARATOR)
+        .filter_map(|chunk| {
+            let chunk = chunk.trim_start_matches(['\n', '\x0c']);
+            let (header, body) = chunk.split_once('\n')?;
+            let (manual, node, next) = parse_header(header)?;
+            Some((manual, node, next, body))
+        })
+        .collect();
+
+    let mut commands = dir_entries(&content);
+    if let Some(command) = commands.remove(TOP_NODE) {
+        let next = nodes
+            .iter()
+            .find(|&&(_, node, _, _)| node == TOP_NODE)
+            .and_then(|&(_, _, next, _)| next);
+        if let Some(next) = next {
+            commands.entry(next.to_string()).or_insert(command);
+        }
+    }
+
+    let mut fnames = HashSet::new();
+    nodes
+        .into_iter()
+        .filter(|&(_, node, _, _)| node != TOP_NODE)
+        .filter_map(|(manual, node, _, body)| {
+            let command = commands.get(node).map(String::as_str);
+            let mut doc = parse_node(manual, node, body, command, stemmer)?;
+            if !fnames.insert(doc.fname.clone()) {
+                doc.fname = (2..)
+                    .map(|n| info_fname(manual, node, Some(n)))
+                    .find(|fname| !fnames.contains(fname))?;
+                fnames.insert(doc.fname.clone());
+            }
+            Some(doc)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::make_stemmer;
+
+    const MANUAL: &str = "This is grep.info.\n\
+                          START-INFO-DIR-ENTRY\n\
+                          * grep: (grep).                 Print lines matching a pattern.\n\
+                          * Grep options: (grep)Options.  Command-line options.\n\
+                          END-INFO-DIR-ENTRY\n\
+                          \x1f\nFile: grep.info,  Node: Top,  Next: Usage,  Up: (dir)\n\n\
+                          grep\n****\n\n* Menu:\n\n* Usage::\n\
+                          \x1f\nFile: grep.info,  Node: Usage,  Next: Options,  Prev: Top\n\n\
+                          1 Usage\n*******\n\n     grep [OPTION...] PATTERNS [FILE...]\n\n\
+                          Searches files, see find(1).\n\
+                          \x1f\nFile: grep.info,  Node: Usage!,  Prev: Usage\n\n\
+                          Same file name as Usage.\n";
+
+    #[test]
+    fn recognises_info_files() {
+        for name in ["grep.info.gz", "find.info-1.gz", "dir", "ed.info"] {
+            assert!(is_info_file(Path::new(name)), "{name}");
+        }
+        for name in ["find.info-a.gz", "grep.1.gz", "info.txt"] {
+            assert!(!is_info_file(Path::new(name)), "{name}");
+        }
+    }
+
+    #[test]
+    fn parses_node_headers() {
+        let header = "File: coreutils.info,  Node: cp invocation,  Next: dd invocation,  Up: Basic";
+        assert_eq!(
+            parse_header(header),
+            Some(("coreutils", "cp invocation", Some("dd invocation")))
+        );
+        let header = "File: ed.info,  Node: Top";
+        assert_eq!(parse_header(header), Some(("ed", "Top", None)));
+        assert_eq!(parse_header("Tag Table:"), None);
+    }
+
+    #[test]
+    fn maps_dir_entries_to_nodes() {
+        let content = "START-INFO-DIR-ENTRY\n\
+                       * cp: (coreutils)cp invocation.     Copy files.\n\
+                       * ed: (ed).                         The editor.\n\
+                       * Common options: (coreutils)Common options.\n\
+                       END-INFO-DIR-ENTRY\n\
+                       * ls: (coreutils)ls invocation.     Outside the entry.\n";
+        let commands = dir_entries(content);
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands["cp invocation"], "cp");
+        assert_eq!(commands["Top"], "ed");
+    }
+
+    #[test]
+    fn cleans_titles_and_names() {
+        assert_eq!(plain_title("11.1 ‘cp’: Copy files"), "cp: Copy files");
+        assert_eq!(plain_title("2 `ed' Commands"), "ed Commands");
+        assert_eq!(plain_title("Overview"), "Overview");
+        assert_eq!(slug("cp invocation (GNU)"), "cp-invocation-gnu");
+        let fname = info_fname("Coreutils", "cp invocation", None);
+        assert_eq!(fname, "coreutils-cp-invocation.info");
+        assert_eq!(info_fname("grep", "Usage", Some(2)), "grep-usage-2.info");
+    }
+
+    #[test]
+    fn cuts_long_fnames_to_length() {
+        let node = "a very long node name with many words ".repeat(3);
+        for n in [None, Some(12)] {
+            let fname = info_fname("coreutils", &node, n);
+            assert!(fname.len() <= INFO_FNAME_MAX_BYTES, "{fname}");
+            assert!(!fname.contains("-."), "{fname}");
+        }
+        let fname = info_fname("é", &"é".repeat(40), None);
+        assert!(fname.len() <= INFO_FNAME_MAX_BYTES && fname.ends_with("é.info"));
+    }
+
+    #[test]
+    fn skips_menu_only_nodes() {
+        let body = "\ngrep\n****\n\n* Menu:\n\n* Usage::  How to run it.\n";
+        assert!(parse_node("grep", "Top", body, None, &make_stemmer()).is_none());
+    }
+
+    #[test]
+    fn splits_a_manual_into_nodes() {
+        let dir = std::env::temp_dir().join(format!("info-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("grep.info");
+        std::fs::write(&path, MANUAL).unwrap();
+        let docs = parse_info_docs(&path, &make_stemmer());
+        let part = parse_info_docs(&dir.join("grep.info-1"), &make_stemmer());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let fnames: Vec<&str> = docs.iter().map(|d| d.fname.as_str()).collect();
+        assert_eq!(fnames, ["grep-usage.info", "grep-usage-2.info"]);
+        let usage = &docs[0];
+        assert_eq!(usage.name_desc_raw, "Usage");
+        assert_eq!(usage.cmd_name, "grep");
+        assert_eq!(usage.synopsis_tf.get("pattern"), Some(&1));
+        assert_eq!(usage.refs.mentions, ["find.1"]);
+        assert!(part.is_empty());
+    }
+}
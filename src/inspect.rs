@@ -0,0 +1,204 @@
+//! Looks inside a loaded index: file layout, the largest posting lists and
+//! a consistency check of the on-disk structures.
+
+use crate::doc::doc_type_multiplier;
+use crate::index::{read_footer, weighted, MmapIndex, Segment};
+use crate::profile::RankingProfile;
+
+/// Bytes per posting: doc id plus four field scores.
+const POSTING_BYTES: u64 = 4 + 4 * 4;
+/// Problems reported per kind before the rest are only counted.
+const MAX_REPORTED: usize = 10;
+
+/// Where each part of a segment file starts and how large it is.
+pub struct Layout {
+    pub version: u32,
+    pub file_size: u64,
+    /// (part, byte offset, byte length), in file order.
+    pub sections: Vec<(&'static str, u64, u64)>,
+}
+
+pub fn layout(segment: &Segment) -> Layout {
+    let raw = &segment.mmap[..];
+    let file_size = raw.len() as u64;
+    let footer = read_footer(raw);
+    let (postings_start, texts_start) = posting_bounds(segment);
+
+    let bounds = [
+        ("header", 0),
+        ("documents", 8),
+        ("postings", postings_start),
+        ("page texts", texts_start),
+        ("text table", footer.text_table as u64),
+        ("associations", footer.assoc as u64),
+        ("vectors", footer.vectors as u64),
+        ("dictionary", footer.dict as u64),
+        ("footer", file_size - 32),
+    ];
+    let sections = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, &(name, start))| {
+            let end = bounds.get(i + 1).map_or(file_size, |&(_, next)| next);
+            (name, start, end.saturating_sub(start))
+        })
+        .collect();
+
+    let mut version = [0u8; 4];
+    version.copy_from_slice(&raw[4..8]);
+    Layout {
+        version: u32::from_le_bytes(version),
+        file_size,
+        sections,
+    }
+}
+
+/// The byte range holding posting lists: from the lowest dictionary offset
+/// to the first page text, which `save_index` writes right after them.
+fn posting_bounds(segment: &Segment) -> (u64, u64) {
+    let footer = read_footer(&segment.mmap);
+    let texts_start = segment
+        .text_table
+        .iter()
+        .map(|&(offset, _)| offset)
+        .min()
+        .unwrap_or(footer.text_table as u64);
+    let postings_start = segment
+        .dict
+        .values()
+        .map(|&(offset, _)| offset)
+        .min()
+        .unwrap_or(texts_start)
+        .min(texts_start);
+    (postings_start, texts_start)
+}
+
+/// Where `term`'s posting list lies in `segment`: (byte offset, postings).
+pub fn posting_location(segment: &Segment, term: &str) -> Option<(u64, u32)> {
+    segment.dict.get(term).copied()
+}
+
+/// The `n` terms found in the most documents, most first.
+pub fn largest_postings(index: &MmapIndex, n: usize) -> Vec<(&str, u32)> {
+    let mut terms: Vec<(&str, u32)> = index
+        .inverted_dict
+        .iter()
+        .map(|(term, &df)| (term.as_str(), df))
+        .collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    terms.truncate(n);
+    terms
+}
+
+/// Problems of one kind, the first `MAX_REPORTED` spelt out.
+#[derive(Default)]
+pub struct Findings {
+    pub count: usize,
+    pub examples: Vec<String>,
+}
+
+impl Findings {
+    fn add(&mut self, problem: impl FnOnce() -> String) {
+        if self.examples.len() < MAX_REPORTED {
+            self.examples.push(problem());
+        }
+        self.count += 1;
+    }
+}
+
+/// Outcome of `check`, one entry per kind of problem looked for.
+pub struct CheckReport {
+    pub checks: Vec<(&'static str, Findings)>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|(_, f)| f.count == 0)
+    }
+}
+
+/// Verifies that dictionary offsets and page texts lie inside their parts
+/// of each segment file, that every doc id refers to a document, and that
+/// posting lists are in the best-first order `build_index` writes them in.
+pub fn check(index: &MmapIndex) -> CheckReport {
+    let default_profile = RankingProfile::default();
+
+    let mut offsets = Findings::default();
+    let mut doc_ids = Findings::default();
+    let mut order = Findings::default();
+    let mut texts = Findings::default();
+    let mut refs = Findings::default();
+
+    for segment in index.segments() {
+        let footer = read_footer(&segment.mmap);
+        let (_, texts_start) = posting_bounds(segment);
+        let type_mults: Vec<f32> = segment
+            .fnames
+            .iter()
+            .map(|fname| doc_type_multiplier(fname, &default_profile))
+            .collect();
+        let path = &segment.path;
+
+        let mut terms: Vec<(&String, &(u64, u32))> = segment.dict.iter().collect();
+        terms.sort();
+        for (term, &(offset, len)) in terms {
+            // A corrupt entry can name a range past the end of any file
+            let Some(end) = (len as u64)
+                .checked_mul(POSTING_BYTES)
+                .and_then(|size| offset.checked_add(size))
+            else {
+                offsets.add(|| format!("{path}: `{term}`: {len} postings at {offset} overflow"));
+                continue;
+            };
+            if len == 0 || offset < 8 || end > texts_start {
+                offsets.add(|| format!("{path}: `{term}`: {len} postings at {offset}..{end}"));
+                continue;
+            }
+            let Some(postings) = segment.read_postings(term) else {
+                continue;
+            };
+            let mut prev = f32::INFINITY;
+            let mut sorted = true;
+            for (doc_id, parts) in postings {
+                let Some(&mult) = type_mults.get(doc_id as usize) else {
+                    doc_ids.add(|| format!("{path}: `{term}` lists doc {doc_id}"));
+                    continue;
+                };
+                let score = weighted(&parts, &default_profile) * mult;
+                sorted &= score <= prev;
+                prev = score;
+            }
+            if !sorted {
+                order.add(|| format!("{path}: `{term}`"));
+            }
+        }
+
+        for (doc_id, &(offset, len)) in segment.text_table.iter().enumerate() {
+            let end = offset.checked_add(len as u64);
+            if offset < texts_start || end.is_none_or(|end| end > footer.text_table as u64) {
+                let fname = &segment.fnames[doc_id];
+                let end = end.map_or_else(|| "overflow".to_string(), |end| end.to_string());
+                texts.add(|| format!("{path}: {fname}: text at {offset}..{end}"));
+            }
+        }
+    }
+
+    let doc_count = index.doc_map.len();
+    let listed = index.cmd_name_index.values().flatten();
+    let linked = index.see_also.iter().flatten();
+    for &doc_id in listed.chain(linked) {
+        if doc_id as usize >= doc_count {
+            refs.add(|| format!("doc {doc_id}"));
+        }
+    }
+
+    CheckReport {
+        checks: vec![
+            ("dictionary offsets in range", offsets),
+            ("posting doc ids valid", doc_ids),
+            ("postings sorted best first", order),
+            ("page texts in range", texts),
+            ("command names and SEE ALSO link valid docs", refs),
+        ],
+    }
+}
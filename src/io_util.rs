@@ -42,6 +42,23 @@ pub fn write_tf_map<W: Write>(w: &mut W, map: &HashMap<String, u32>) -> io::Resu
     Ok(())
 }
 
+pub fn write_str_list<W: Write>(w: &mut W, list: &[String]) -> io::Result<()> {
+    write_u32(w, list.len() as u32)?;
+    for s in list {
+        write_str(w, s)?;
+    }
+    Ok(())
+}
+
+pub fn read_str_list<R: Read>(r: &mut R) -> io::Result<Vec<String>> {
+    let n = read_u32(r)? as usize;
+    let mut list = Vec::with_capacity(n);
+    for _ in 0..n {
+        list.push(read_str(r)?);
+    }
+    Ok(list)
+}
+
 pub fn read_tf_map<R: Read>(r: &mut R) -> io::Result<HashMap<String, u32>> {
     let n = read_u32(r)? as usize;
     let mut map = HashMap::with_capacity(n);
@@ -1,7 +1,31 @@
+pub mod apropos;
+pub mod assets;
+pub mod assoc;
+pub mod cmdline;
 pub mod constants;
 pub mod crawl;
 pub mod doc;
+#[cfg(feature = "embeddings")]
+pub mod embed;
+pub mod eval;
+pub mod export;
+pub mod graph;
 pub mod index;
+pub mod info;
+pub mod inspect;
 pub mod io_util;
+pub mod overstrike;
+pub mod profile;
+pub mod querylog;
+pub mod related;
+pub mod rpc;
 pub mod search;
+pub mod segment;
+pub mod shell;
+pub mod snippet;
+pub mod spell;
+pub mod suggest;
+pub mod synonyms;
 pub mod text;
+pub mod tldr;
+pub mod tui;
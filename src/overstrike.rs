@@ -0,0 +1,125 @@
+//! Unix backspace ("overstrike") formatting as printed by `man`:
+//! `X\x08X` is bold and `_\x08X` is underlined.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Style {
+    Plain,
+    Bold,
+    Underline,
+}
+
+/// One output line as runs of equally styled text.
+pub type StyledLine = Vec<(Style, String)>;
+
+/// Splits `raw` into lines of styled runs with the backspaces removed.
+pub fn parse_man_formatting(raw: &str) -> Vec<StyledLine> {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut lines: Vec<StyledLine> = vec![Vec::new()];
+    let mut i = 0;
+    while i < chars.len() {
+        let (style, c) = if i + 2 < chars.len() && chars[i + 1] == '\x08' {
+            let style = if chars[i] == '_' {
+                Style::Underline
+            } else {
+                Style::Bold
+            };
+            i += 3;
+            (style, chars[i - 1])
+        } else {
+            i += 1;
+            (Style::Plain, chars[i - 1])
+        };
+
+        if c == '\n' {
+            lines.push(Vec::new());
+            continue;
+        }
+        let line = lines.last_mut().expect("always at least one line");
+        match line.last_mut() {
+            Some((run_style, text)) if *run_style == style => text.push(c),
+            _ => line.push((style, c.to_string())),
+        }
+    }
+    lines
+}
+
+fn escape_html(text: &str, buf: &mut String) {
+    for c in text.chars() {
+        match c {
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '&' => buf.push_str("&amp;"),
+            _ => buf.push(c),
+        }
+    }
+}
+
+/// Converts overstrike formatting into escaped HTML with `<b>` / `<u>` tags.
+pub fn to_html(raw: &str) -> String {
+    let mut html = String::with_capacity(raw.len() * 2);
+    for (n, line) in parse_man_formatting(raw).iter().enumerate() {
+        if n > 0 {
+            html.push('\n');
+        }
+        for (style, text) in line {
+            let (open, close) = match style {
+                Style::Plain => ("", ""),
+                Style::Bold => ("<b>", "</b>"),
+                Style::Underline => ("<u>", "</u>"),
+            };
+            html.push_str(open);
+            escape_html(text, &mut html);
+            html.push_str(close);
+        }
+    }
+    html
+}
+
+/// The text of `raw` with the formatting removed.
+pub fn strip(raw: &str) -> String {
+    parse_man_formatting(raw)
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|(_, text)| text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(style: Style, text: &str) -> (Style, String) {
+        (style, text.to_string())
+    }
+
+    #[test]
+    fn parses_bold_and_underline_runs() {
+        let lines = parse_man_formatting("c\x08cp\x08p _\x08f\n_\x08_x");
+        assert_eq!(
+            lines,
+            [
+                vec![
+                    run(Style::Bold, "cp"),
+                    run(Style::Plain, " "),
+                    run(Style::Underline, "f"),
+                ],
+                vec![run(Style::Underline, "_"), run(Style::Plain, "x")],
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes_html_inside_tags() {
+        let html = to_html("<\x08<a\x08a>\x08> _\x08&\nplain");
+        assert_eq!(html, "<b>&lt;a&gt;</b> <u>&amp;</u>\nplain");
+    }
+
+    #[test]
+    fn strips_formatting() {
+        assert_eq!(strip("N\x08NA\x08AME\n  _\x08ls"), "NAME\n  ls");
+    }
+}
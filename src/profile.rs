@@ -0,0 +1,128 @@
+//! Query-time ranking parameters. The defaults reproduce the hand-tuned
+//! constants; `train` fits them to judged or clicked queries and writes a
+//! profile that `load_index` picks up from `RANKING_PROFILE_PATH` next to
+//! the index.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::constants::*;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingProfile {
+    // Field weights applied to the per-field BM25 parts of each posting
+    pub weight_cmd_name: f32,
+    pub weight_name_desc: f32,
+    pub weight_synopsis: f32,
+    pub weight_body: f32,
+
+    // `doc_type_multiplier` factors
+    pub section_user: f32,     // 1: user commands
+    pub section_admin: f32,    // 8: sysadmin commands
+    pub section_config: f32,   // 5: config files
+    pub section_dev: f32,      // 2, 3: syscalls / library calls
+    pub section_misc: f32,     // 4, 6, 7
+    pub section_examples: f32, // tldr pages
+    pub section_info: f32,     // info nodes
+    pub section_other: f32,
+    pub vip_boost: f32,
+    /// Exponent on the PageRank authority prior; 0 disables it.
+    pub authority_weight: f32,
+
+    // Rerank and expansion
+    pub semantic_weight: f32,
+    pub assoc_rerank_weight: f32,
+    /// Prefix matches are scaled by `prefix_decay ^ (extra chars + 1)`.
+    pub prefix_decay: f32,
+    pub fuzzy_penalty: f32,
+}
+
+impl Default for RankingProfile {
+    fn default() -> Self {
+        RankingProfile {
+            weight_cmd_name: WEIGHT_CMD_NAME,
+            weight_name_desc: WEIGHT_NAME_DESC,
+            weight_synopsis: WEIGHT_SYNOPSIS,
+            weight_body: WEIGHT_BODY,
+            section_user: 4.0,
+            section_admin: 2.5,
+            section_config: 1.2,
+            section_dev: 0.8,
+            section_misc: 0.6,
+            section_examples: 3.0,
+            section_info: 1.0,
+            section_other: 0.8,
+            vip_boost: 5.0,
+            authority_weight: AUTHORITY_WEIGHT,
+            semantic_weight: SEMANTIC_WEIGHT,
+            assoc_rerank_weight: ASSOC_RERANK_WEIGHT,
+            prefix_decay: PREFIX_DECAY,
+            fuzzy_penalty: FUZZY_PENALTY,
+        }
+    }
+}
+
+impl RankingProfile {
+    /// The defaults, overridden by the JSON profile at `path` if it exists.
+    /// Keys missing from the file keep their default value; a file that
+    /// cannot be read is reported and ignored.
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return RankingProfile::default();
+        }
+        Self::read(&path.to_string_lossy()).unwrap_or_else(|e| {
+            eprintln!("Ignoring ranking profile: {e}");
+            RankingProfile::default()
+        })
+    }
+
+    /// Reads the JSON profile at `path`, which must exist.
+    pub fn read(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path}: {e}")))
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json)
+    }
+
+    /// Every tunable parameter with its value, in the order of `params_mut`.
+    pub fn params(&self) -> Vec<(&'static str, f32)> {
+        let mut copy = self.clone();
+        copy.params_mut()
+            .into_iter()
+            .map(|(name, value)| (name, *value))
+            .collect()
+    }
+
+    /// Every tunable parameter by name, in a fixed order.
+    pub fn params_mut(&mut self) -> [(&'static str, &mut f32); 18] {
+        [
+            ("weight_cmd_name", &mut self.weight_cmd_name),
+            ("weight_name_desc", &mut self.weight_name_desc),
+            ("weight_synopsis", &mut self.weight_synopsis),
+            ("weight_body", &mut self.weight_body),
+            ("section_user", &mut self.section_user),
+            ("section_admin", &mut self.section_admin),
+            ("section_config", &mut self.section_config),
+            ("section_dev", &mut self.section_dev),
+            ("section_misc", &mut self.section_misc),
+            ("section_examples", &mut self.section_examples),
+            ("section_info", &mut self.section_info),
+            ("section_other", &mut self.section_other),
+            ("vip_boost", &mut self.vip_boost),
+            ("authority_weight", &mut self.authority_weight),
+            ("semantic_weight", &mut self.semantic_weight),
+            ("assoc_rerank_weight", &mut self.assoc_rerank_weight),
+            ("prefix_decay", &mut self.prefix_decay),
+            ("fuzzy_penalty", &mut self.fuzzy_penalty),
+        ]
+    }
+}
@@ -0,0 +1,152 @@
+//! Append-only JSONL log of searches and page opens, for building judgment
+//! sets (see `eval`) and finding zero-result queries.
+//!
+//! Client ids are a salted hash of the client address; the salt is random
+//! per process and never written out, so ids only link requests made
+//! against the same server run.
+//!
+//! Lines are written by a thread of their own, so recording never blocks
+//! the caller on file I/O.
+
+use std::collections::hash_map::RandomState;
+use std::fs::{self, File, OpenOptions};
+use std::hash::BuildHasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::constants::{QUERY_LOG_KEEP, QUERY_LOG_MAX_BYTES};
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent<'a> {
+    Search {
+        query: &'a str,
+        tokens: &'a [String],
+        total: usize,
+        offset: usize,
+        /// fnames of the returned page, in rank order.
+        results: Vec<&'a str>,
+        took_ms: f64,
+    },
+    Open {
+        fname: &'a str,
+        /// The query and 0-based rank the page was opened from, when the client sent them.
+        query: Option<&'a str>,
+        rank: Option<usize>,
+    },
+}
+
+#[derive(Serialize)]
+struct LogLine<'a> {
+    ts_ms: u128,
+    client: &'a str,
+    #[serde(flatten)]
+    event: &'a LogEvent<'a>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+pub struct QueryLog {
+    path: PathBuf,
+    salt: RandomState,
+    lines: Sender<Vec<u8>>,
+}
+
+impl QueryLog {
+    /// Opens (or creates) the log at `path` for appending and starts the
+    /// thread that writes to it.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+        let mut log = LogFile {
+            path: path.clone(),
+            file,
+            size,
+        };
+        let (lines, received) = mpsc::channel::<Vec<u8>>();
+        thread::spawn(move || {
+            for line in received {
+                // A failing write must never fail the request it records
+                if let Err(e) = log.append(&line) {
+                    eprintln!("Failed to write {}: {e}", log.path.display());
+                }
+            }
+        });
+        Ok(QueryLog {
+            path,
+            salt: RandomState::new(),
+            lines,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Anonymous, per-run stable id for a client address.
+    pub fn client_id(&self, addr: &str) -> String {
+        format!("{:016x}", self.salt.hash_one(addr))
+    }
+
+    /// Queues one event for the writer thread.
+    pub fn record(&self, client: &str, event: &LogEvent) -> io::Result<()> {
+        let ts_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let mut line = serde_json::to_vec(&LogLine {
+            ts_ms,
+            client,
+            event,
+        })?;
+        line.push(b'\n');
+        self.lines
+            .send(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "log writer stopped"))
+    }
+}
+
+impl LogFile {
+    /// Appends one line, rotating first if it would push the file past
+    /// `QUERY_LOG_MAX_BYTES`.
+    fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > QUERY_LOG_MAX_BYTES {
+            self.rotate()?;
+            self.file = open_append(&self.path)?;
+            self.size = 0;
+        }
+        self.file.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shifts `log.N` to `log.N+1` (dropping the oldest) and `log` to `log.1`.
+    fn rotate(&self) -> io::Result<()> {
+        let numbered = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{n}"));
+            PathBuf::from(name)
+        };
+        for n in (1..QUERY_LOG_KEEP).rev() {
+            let from = numbered(n);
+            if from.exists() {
+                fs::rename(&from, numbered(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, numbered(1))
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
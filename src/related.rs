@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::constants::RELATED_MIN_SIMILARITY;
+use crate::index::{MmapIndex, TermVector};
+
+#[derive(Serialize)]
+pub struct RelatedPage {
+    pub doc_id: u32,
+    pub fname: String,
+    pub name_desc: String,
+    /// Cosine similarity of the two pages' term vectors.
+    pub score: f32,
+}
+
+#[derive(Serialize, Default)]
+pub struct Related {
+    /// The page's SEE ALSO targets that exist in the index, in page order.
+    pub see_also: Vec<RelatedPage>,
+    /// The tldr page and info node of a man page's command, or the man pages
+    /// a tldr page has examples for or an info node documents.
+    pub linked: Vec<RelatedPage>,
+    /// The most content-similar other pages, best first.
+    pub similar: Vec<RelatedPage>,
+}
+
+/// Dot product of two unit term vectors sorted by term.
+pub fn similarity(a: &TermVector, b: &TermVector) -> f32 {
+    let (mut i, mut j) = (0, 0);
+    let mut dot = 0.0f32;
+    while i < a.len() && j < b.len() {
+        match a[i].0.cmp(&b[j].0) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                dot += a[i].1 * b[j].1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    dot
+}
+
+/// Pages linked to `doc_id` through `MmapIndex::example_pages` (both ways)
+/// and `MmapIndex::info_links`.
+fn linked_pages(index: &MmapIndex, doc_id: u32) -> Vec<u32> {
+    let mut linked: Vec<u32> = match index.example_pages[doc_id as usize] {
+        Some(page) if page == doc_id => index.example_targets[doc_id as usize].clone(),
+        page => page.into_iter().collect(),
+    };
+    linked.extend(index.info_links[doc_id as usize]);
+    linked
+}
+
+fn page(index: &MmapIndex, doc_id: u32, score: f32) -> RelatedPage {
+    RelatedPage {
+        doc_id,
+        fname: index.doc_map[doc_id as usize].clone(),
+        name_desc: index.name_descs[doc_id as usize].clone(),
+        score,
+    }
+}
+
+/// SEE ALSO targets and linked pages of `doc_id` plus up to `limit` similar
+/// pages that are not already among them, one per command name.
+pub fn related(index: &MmapIndex, doc_id: u32, limit: usize) -> Related {
+    let Some(vector) = index.term_vectors.get(doc_id as usize) else {
+        return Related::default();
+    };
+    let see_also = &index.see_also[doc_id as usize];
+    let linked = linked_pages(index, doc_id);
+
+    let mut best_for_base: HashMap<&str, (u32, f32)> = HashMap::new();
+    for (other, other_vector) in index.term_vectors.iter().enumerate() {
+        let other = other as u32;
+        if other == doc_id || see_also.contains(&other) || linked.contains(&other) {
+            continue;
+        }
+        let score = similarity(vector, other_vector);
+        if score < RELATED_MIN_SIMILARITY {
+            continue;
+        }
+        let base = index.doc_map[other as usize]
+            .split('.')
+            .next()
+            .unwrap_or("");
+        let entry = best_for_base
+            .entry(base)
+            .or_insert((other, f32::NEG_INFINITY));
+        if score > entry.1 {
+            *entry = (other, score);
+        }
+    }
+
+    let mut similar: Vec<(u32, f32)> = best_for_base.into_values().collect();
+    similar.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    similar.truncate(limit);
+
+    let with_score = |target: u32| {
+        let score = similarity(vector, &index.term_vectors[target as usize]);
+        page(index, target, score)
+    };
+    Related {
+        see_also: see_also.iter().map(|&target| with_score(target)).collect(),
+        linked: linked.into_iter().map(with_score).collect(),
+        similar: similar
+            .into_iter()
+            .map(|(other, score)| page(index, other, score))
+            .collect(),
+    }
+}
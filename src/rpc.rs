@@ -0,0 +1,327 @@
+//! Newline-delimited JSON-RPC 2.0 over a loaded index, as served by the
+//! `daemon` binary for editor plugins and shell widgets.
+//!
+//! Methods:
+//!   search       {query, limit?, offset?, sections?, sources?}  -> SearchReport
+//!   suggest      {prefix, limit?}                               -> [Suggestion]
+//!   get-page     {fname, width?, html?}                         -> Page
+//!   explain-cmd  {command}                                      -> CommandHelp | null
+
+use std::io::{self, BufRead, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::cmdline::explain_command;
+use crate::constants::*;
+use crate::doc::{is_man_page, render_formatted};
+use crate::graph::split_page;
+use crate::index::MmapIndex;
+use crate::overstrike::{strip, to_html};
+use crate::search::{search_report, ReportOptions};
+use crate::spell::SpellChecker;
+use crate::suggest::Suggester;
+use crate::tldr::Example;
+
+// Standard JSON-RPC error codes
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const INTERNAL_ERROR: i32 = -32603;
+/// Application error: the requested page is not in the index.
+const PAGE_NOT_FOUND: i32 = 1;
+
+#[derive(Serialize)]
+struct RpcError {
+    code: i32,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: Option<String>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    id: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+    /// e.g. `["1", "8"]`; empty keeps every section
+    #[serde(default)]
+    sections: Vec<String>,
+    /// e.g. `["coreutils"]`; empty keeps every source
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SuggestParams {
+    prefix: String,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    fname: String,
+    width: Option<u16>,
+    /// Return `<b>`/`<u>` HTML instead of plain text.
+    #[serde(default)]
+    html: bool,
+}
+
+#[derive(Deserialize)]
+struct ExplainCmdParams {
+    command: String,
+}
+
+#[derive(Serialize)]
+pub struct Page {
+    pub fname: String,
+    pub name_desc: String,
+    pub text: String,
+    /// Example commands from the page's tldr page, if it has one.
+    pub examples: Vec<Example>,
+}
+
+/// Everything a request needs, built once when the daemon starts.
+pub struct RpcService {
+    index: MmapIndex,
+    suggester: Suggester,
+    speller: SpellChecker,
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn to_value(result: impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| RpcError::new(INTERNAL_ERROR, e.to_string()))
+}
+
+impl RpcService {
+    pub fn new(index: MmapIndex) -> Self {
+        RpcService {
+            suggester: Suggester::build(&index),
+            speller: SpellChecker::build(&index),
+            index,
+        }
+    }
+
+    /// Answers requests read from `reader`, one per line, until EOF.
+    pub fn serve(&self, reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_line(&line) {
+                writeln!(writer, "{response}")?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Answers one request; `None` for notifications, which get no reply.
+    pub fn handle_line(&self, line: &str) -> Option<String> {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let code = if e.is_data() {
+                    INVALID_REQUEST
+                } else {
+                    PARSE_ERROR
+                };
+                return Some(reply(Value::Null, Err(RpcError::new(code, e.to_string()))));
+            }
+        };
+        if request.jsonrpc.as_deref() != Some("2.0") {
+            let error = RpcError::new(INVALID_REQUEST, "`jsonrpc` must be \"2.0\"");
+            return Some(reply(request.id.unwrap_or(Value::Null), Err(error)));
+        }
+
+        let result = self.call(&request.method, request.params);
+        request.id.map(|id| reply(id, result))
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "search" => {
+                let p: SearchParams = parse(params)?;
+                let options = ReportOptions {
+                    top_k: p
+                        .limit
+                        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+                        .clamp(1, SEARCH_MAX_LIMIT),
+                    offset: p.offset,
+                    sections: p.sections,
+                    sources: p.sources,
+                };
+                to_value(search_report(
+                    &p.query,
+                    &self.index,
+                    &options,
+                    Some(&self.speller),
+                ))
+            }
+            "suggest" => {
+                let p: SuggestParams = parse(params)?;
+                let limit = p
+                    .limit
+                    .unwrap_or(SUGGEST_DEFAULT_LIMIT)
+                    .min(SUGGEST_MAX_LIMIT);
+                to_value(self.suggester.suggest(&p.prefix, limit))
+            }
+            "get-page" => {
+                let p: PageParams = parse(params)?;
+                to_value(self.page(&p)?)
+            }
+            "explain-cmd" => {
+                let p: ExplainCmdParams = parse(params)?;
+                to_value(explain_command(&self.index, &p.command))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method `{method}`"),
+            )),
+        }
+    }
+
+    /// The page rendered by `man`, or its indexed text when that fails or
+    /// it is not a man page.
+    fn page(&self, p: &PageParams) -> Result<Page, RpcError> {
+        let doc_id = self
+            .index
+            .find_doc(&p.fname)
+            .ok_or_else(|| RpcError::new(PAGE_NOT_FOUND, format!("no page `{}`", p.fname)))?;
+        let rendered = split_page(&p.fname)
+            .filter(|_| is_man_page(&p.fname))
+            .and_then(|(name, section)| {
+                render_formatted(name, section, p.width.unwrap_or(PAGE_RENDER_WIDTH))
+            });
+        let text = match rendered {
+            Some(raw) if p.html => to_html(&raw),
+            Some(raw) => strip(&raw),
+            None if p.html => to_html(self.index.doc_text(doc_id)),
+            None => self.index.doc_text(doc_id).to_string(),
+        };
+        Ok(Page {
+            fname: p.fname.clone(),
+            name_desc: self.index.name_descs[doc_id as usize].clone(),
+            text,
+            examples: self.index.examples_for(doc_id).to_vec(),
+        })
+    }
+}
+
+fn reply(id: Value, result: Result<Value, RpcError>) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    };
+    response.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{build_from_export, read_jsonl};
+    use crate::index::{load_index, save_index};
+
+    const EXPORT: &str = concat!(
+        r#"{"kind":"doc","doc_id":0,"fname":"cp.1","name":"cp","section":"1","#,
+        r#""cmd_name":"cp","description":"copy files","desc_len":2,"synopsis_len":0,"#,
+        r#""body_len":2,"authority":1.0}"#,
+        "\n",
+        r#"{"kind":"term","term":"cp","postings":[[0,[1.0,0.0,0.0,0.0]]]}"#,
+        "\n",
+        r#"{"kind":"term","term":"copi","postings":[[0,[0.0,1.0,0.0,1.0]]]}"#,
+    );
+
+    /// A service over a one-page index saved under a per-test name.
+    fn service(name: &str) -> RpcService {
+        let export = read_jsonl(EXPORT.as_bytes()).unwrap();
+        let index = build_from_export(export).unwrap();
+        let file = format!("rpc-{name}-{}.idx", std::process::id());
+        let path = std::env::temp_dir().join(file);
+        let path = path.to_str().unwrap();
+        save_index(path, &index).unwrap();
+        let index = load_index(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        RpcService::new(index)
+    }
+
+    fn call(service: &RpcService, line: &str) -> Value {
+        let reply = service.handle_line(line).expect("a reply");
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    fn request(id: Value, method: &str, params: Value) -> String {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string()
+    }
+
+    #[test]
+    fn tells_parse_errors_from_invalid_requests() {
+        let service = service("invalid");
+        let reply = call(&service, "{not json");
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+
+        for line in [r#"{"jsonrpc":"2.0","id":1}"#, "[1, 2]"] {
+            let reply = call(&service, line);
+            assert_eq!(reply["error"]["code"], INVALID_REQUEST, "{line}");
+        }
+        let reply = call(&service, r#"{"jsonrpc":"1.0","id":7,"method":"suggest"}"#);
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+    }
+
+    #[test]
+    fn notifications_get_no_reply() {
+        let service = service("notify");
+        for method in ["suggest", "no-such-method"] {
+            let line = json!({ "jsonrpc": "2.0", "method": method, "params": { "prefix": "c" } });
+            assert_eq!(service.handle_line(&line.to_string()), None);
+        }
+    }
+
+    #[test]
+    fn reports_method_and_params_errors() {
+        let service = service("errors");
+        let cases = [
+            ("frobnicate", json!({}), METHOD_NOT_FOUND),
+            ("search", json!({}), INVALID_PARAMS),
+            ("get-page", json!({ "fname": "mv.1" }), PAGE_NOT_FOUND),
+        ];
+        for (method, params, code) in cases {
+            let reply = call(&service, &request(json!(1), method, params));
+            assert_eq!(reply["error"]["code"], code, "{method}");
+        }
+    }
+
+    #[test]
+    fn answers_a_search() {
+        let service = service("search");
+        let line = request(json!("a"), "search", json!({ "query": "copy" }));
+        let reply = call(&service, &line);
+        assert_eq!(reply["id"], "a");
+        assert_eq!(reply["result"]["results"][0]["fname"], "cp.1");
+    }
+}
@@ -1,25 +1,49 @@
 use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
 
 use rust_stemmers::Stemmer;
 use serde::Serialize;
 
+use crate::apropos::section_matches;
+use crate::assoc::{cosine, expand_vector};
 use crate::constants::*;
-use crate::index::MmapIndex;
+use crate::doc::doc_type_multiplier;
+#[cfg(feature = "embeddings")]
+use crate::embed::reciprocal_rank_fusion;
+use crate::graph::split_page;
+use crate::index::{FieldScores, MmapIndex};
+use crate::info::is_info_page;
+use crate::snippet::{attach_snippets, Snippet};
+use crate::spell::SpellChecker;
+use crate::synonyms::SynonymTable;
 use crate::text::{edit_distance, make_stemmer, tokenize};
+use crate::tldr::Example;
 
 fn query_idf(token: &str, index: &MmapIndex, n: f32) -> f32 {
-    let df = index
-        .inverted_dict
-        .get(token)
-        .map(|&(_, len)| len)
-        .unwrap_or(1) as f32;
+    let df = index.inverted_dict.get(token).copied().unwrap_or(1) as f32;
     ((n - df + 0.5) / (df + 0.5) + 1.0).ln().max(0.01)
 }
 
+/// How well `desc_tokens` covers query token `tok`: 1.0 for the token
+/// itself, the synonym weight for its best synonym, 0.0 otherwise.
+fn desc_match_weight(tok: &str, desc_tokens: &HashSet<String>, synonyms: &SynonymTable) -> f32 {
+    if desc_tokens.contains(tok) {
+        return 1.0;
+    }
+    synonyms
+        .expand(tok)
+        .iter()
+        .filter(|(syn, _)| desc_tokens.contains(syn))
+        .map(|&(_, w)| w)
+        .fold(0.0, f32::max)
+}
+
 fn semantic_desc_score(
     query_tokens: &HashSet<String>,
     token_idfs: &HashMap<String, f32>,
     name_desc: &str,
+    synonyms: &SynonymTable,
     stemmer: &Stemmer,
 ) -> f32 {
     if name_desc.is_empty() || query_tokens.is_empty() {
@@ -37,7 +61,7 @@ fn semantic_desc_score(
 
     if idfs.len() > 1 {
         for (tok, &idf) in token_idfs.iter() {
-            if idf >= median_idf && !desc_tokens.contains(tok) {
+            if idf >= median_idf && desc_match_weight(tok, &desc_tokens, synonyms) == 0.0 {
                 return 0.0;
             }
         }
@@ -45,19 +69,19 @@ fn semantic_desc_score(
 
     let mut idf_overlap = 0.0f32;
     let mut total_query_idf = 0.0f32;
+    let mut matched = 0.0f32;
     for qt in query_tokens {
         let idf = token_idfs.get(qt).copied().unwrap_or(0.01);
+        let weight = desc_match_weight(qt, &desc_tokens, synonyms);
         total_query_idf += idf;
-        if desc_tokens.contains(qt) {
-            idf_overlap += idf;
-        }
+        idf_overlap += idf * weight;
+        matched += weight;
     }
     if total_query_idf == 0.0 {
         return 0.0;
     }
 
     let coverage = idf_overlap / total_query_idf;
-    let matched = query_tokens.intersection(&desc_tokens).count() as f32;
     let precision = matched / desc_tokens.len() as f32;
 
     if coverage + precision == 0.0 {
@@ -67,23 +91,219 @@ fn semantic_desc_score(
     f1 * f1
 }
 
+/// Highest score first; ties go to the lower doc id so that result order
+/// (and therefore paging) is the same on every call.
+fn by_score_desc(a: &(u32, f32), b: &(u32, f32)) -> std::cmp::Ordering {
+    b.1.partial_cmp(&a.1)
+        .unwrap_or(std::cmp::Ordering::Equal)
+        .then(a.0.cmp(&b.0))
+}
+
+/// Fuses the lexical ranking with the nearest neighbours of the query in
+/// embedding space. Scores become reciprocal-rank-fusion scores.
+#[cfg(feature = "embeddings")]
+fn fuse_vectors(query: &str, index: &MmapIndex, ranked: Vec<(u32, f32)>) -> Vec<(u32, f32)> {
+    let Some(vectors) = &index.vectors else {
+        return ranked;
+    };
+    let Some(query_vec) = vectors.embed_query(query, index) else {
+        return ranked;
+    };
+    let nearest = vectors.nearest(&query_vec, index, VECTOR_TOP_K);
+    let mut fused = reciprocal_rank_fusion(&[&ranked, &nearest]);
+    fused.sort_by(by_score_desc);
+    fused
+}
+
 #[derive(Serialize)]
 pub struct SearchResult {
     pub doc_id: u32,
     pub fname: String,
+    /// Collection the page was crawled from.
+    pub source: String,
     pub name_desc: String,
     pub score: f32,
+    /// Best-matching passage of the page; filled in by `attach_snippets`.
+    pub snippet: Option<Snippet>,
+    /// Example commands from the page's tldr page; filled in by `attach_links`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<Example>,
+    /// The man page of an info node, or the info node of a man page; filled
+    /// in by `attach_links`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info_link: Option<String>,
+}
+
+/// Fills in the examples of each result that has a tldr page, and the page
+/// linked to it in the other of man page and info node.
+pub fn attach_links(results: &mut [SearchResult], index: &MmapIndex) {
+    for r in results {
+        r.examples = index.examples_for(r.doc_id).to_vec();
+        r.info_link =
+            index.info_links[r.doc_id as usize].map(|page| index.doc_map[page as usize].clone());
+    }
+}
+
+/// Why one page scored what it did for a query; see `explain`.
+#[derive(Serialize, Clone)]
+pub struct Explanation {
+    pub doc_id: u32,
+    pub fname: String,
+    /// `doc_type_multiplier` of the page: section factor and VIP boost.
+    pub type_factor: f32,
+    /// PageRank authority of the page (1.0 on average).
+    pub authority: f32,
+    /// `type_factor * authority ^ authority_weight`, applied to every posting.
+    pub prior: f32,
+    pub terms: Vec<TermExplanation>,
+    /// Score added through mined term associations.
+    pub associations: f32,
+    /// Share of the query's IDF the page matched.
+    pub coverage: f32,
+    /// `coverage ^ max(tokens - 1, 2)`
+    pub coverage_factor: f32,
+    pub semantic: f32,
+    pub assoc_similarity: f32,
+    /// `1 + semantic_weight * semantic + assoc_rerank_weight * assoc_similarity`;
+    /// 1.0 when the page was outside the reranked top.
+    pub rerank_factor: f32,
+    pub score: f32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct TermExplanation {
+    pub token: String,
+    pub idf: f32,
+    /// Exact-match scores for command name, description, synopsis and body,
+    /// after field weights and the prior.
+    pub fields: FieldScores,
+    /// Everything the token contributed, prefix/synonym/fuzzy matches included.
+    pub total: f32,
+}
+
+impl Explanation {
+    fn new(index: &MmapIndex, doc_id: u32) -> Self {
+        let fname = index.doc_map[doc_id as usize].clone();
+        Explanation {
+            doc_id,
+            type_factor: doc_type_multiplier(&fname, index.profile()),
+            authority: index.authority[doc_id as usize],
+            prior: index.doc_prior(doc_id),
+            fname,
+            terms: Vec::new(),
+            associations: 0.0,
+            coverage: 0.0,
+            coverage_factor: 0.0,
+            semantic: 0.0,
+            assoc_similarity: 0.0,
+            rerank_factor: 1.0,
+            score: 0.0,
+        }
+    }
+
+    fn add_term(&mut self, token: &str, idf: f32, total: f32, index: &MmapIndex) {
+        let profile = index.profile();
+        let parts = index
+            .get_field_postings(token)
+            .and_then(|postings| postings.into_iter().find(|&(d, _)| d == self.doc_id))
+            .map(|(_, parts)| parts)
+            .unwrap_or_default();
+        let weights = [
+            profile.weight_cmd_name,
+            profile.weight_name_desc,
+            profile.weight_synopsis,
+            profile.weight_body,
+        ];
+        self.terms.push(TermExplanation {
+            token: token.to_string(),
+            idf,
+            fields: std::array::from_fn(|i| parts[i] * weights[i] * self.prior),
+            total,
+        });
+    }
+}
+
+/// Splits `source:<name>` filters off `query`, returning the rest of the
+/// query and the source names.
+pub fn split_filters(query: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut sources = Vec::new();
+    for word in query.split_whitespace() {
+        match word.split_once(':') {
+            Some((key, name)) if key.eq_ignore_ascii_case("source") && !name.is_empty() => {
+                sources.extend(name.split(',').filter(|s| !s.is_empty()).map(String::from));
+            }
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), sources)
+}
+
+/// True if a page from `source` is wanted by a list of source names
+/// (case-insensitive); an empty list wants every page.
+pub fn source_matches(source: &str, wanted: &[String]) -> bool {
+    wanted.is_empty() || wanted.iter().any(|w| w.eq_ignore_ascii_case(source))
 }
 
 pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
+    search_sources(query, index, &[])
+}
+
+/// `search` keeping only pages from one of `sources`, in addition to any
+/// `source:` filters in the query.
+pub fn search_sources(query: &str, index: &MmapIndex, sources: &[String]) -> Vec<SearchResult> {
+    search_filtered(query, index, sources, &[])
+}
+
+/// `search_sources` also keeping only pages from one of `sections` (`3`
+/// matches `3p`); empty keeps all.
+pub fn search_filtered(
+    query: &str,
+    index: &MmapIndex,
+    sources: &[String],
+    sections: &[String],
+) -> Vec<SearchResult> {
+    let (query, mut wanted) = split_filters(query);
+    wanted.extend_from_slice(sources);
+    rank(&query, index, &wanted, sections, None).0
+}
+
+/// Breaks down how `doc_id` was scored for `query`, or `None` if the page
+/// is not among the results `search_filtered` gives with the same filters.
+pub fn explain(
+    query: &str,
+    index: &MmapIndex,
+    sources: &[String],
+    sections: &[String],
+    doc_id: u32,
+) -> Option<Explanation> {
+    let (query, mut wanted) = split_filters(query);
+    wanted.extend_from_slice(sources);
+    let (results, explanation) = rank(&query, index, &wanted, sections, Some(doc_id));
+    let mut explanation = explanation?;
+    explanation.score = results.iter().find(|r| r.doc_id == doc_id)?.score;
+    Some(explanation)
+}
+
+/// Ranks every matching page from `sources` and `sections` (all when
+/// empty) for `query`, also recording how the page `trace` was scored.
+fn rank(
+    query: &str,
+    index: &MmapIndex,
+    sources: &[String],
+    sections: &[String],
+    trace: Option<u32>,
+) -> (Vec<SearchResult>, Option<Explanation>) {
     let stemmer = make_stemmer();
     let query_tokens_vec = tokenize(query, &stemmer);
     if query_tokens_vec.is_empty() {
-        return Vec::new();
+        return (Vec::new(), None);
     }
+    let mut explanation = trace.map(|doc_id| Explanation::new(index, doc_id));
 
     let query_token_set: HashSet<String> = query_tokens_vec.iter().cloned().collect();
     let n = index.doc_map.len() as f32;
+    let profile = index.profile();
 
     let token_idfs: HashMap<String, f32> = query_tokens_vec
         .iter()
@@ -94,7 +314,8 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
     let mut doc_score: HashMap<u32, f32> = HashMap::new();
     let mut doc_matched_idf: HashMap<u32, f32> = HashMap::new();
 
-    for (token, &tok_idf) in query_tokens_vec.iter().zip(token_idfs.values()) {
+    for token in &query_tokens_vec {
+        let tok_idf = token_idfs[token];
         let mut token_posts: HashMap<u32, f32> = HashMap::new();
 
         // Exact match via mmap
@@ -106,9 +327,11 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
 
         // Prefix expansion
         if token.len() >= PREFIX_MIN_LEN && tok_idf > PREFIX_MIN_IDF {
-            for (key, _) in &index.inverted_dict {
+            for key in index.inverted_dict.keys() {
                 if key != token && key.starts_with(token.as_str()) {
-                    let penalty = (0.6f32).powf((key.len() - token.len()) as f32 + 1.0);
+                    let penalty = profile
+                        .prefix_decay
+                        .powf((key.len() - token.len()) as f32 + 1.0);
                     if let Some(postings) = index.get_postings(key) {
                         for (doc_id, score) in postings {
                             *token_posts.entry(doc_id).or_insert(0.0) += score * penalty;
@@ -118,13 +341,28 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
             }
         }
 
+        // Synonym / intent expansion, down-weighted relative to the token itself
+        for (syn, weight) in index.synonyms.expand(token) {
+            if let Some(postings) = index.get_postings(syn) {
+                for (doc_id, score) in postings {
+                    *token_posts.entry(doc_id).or_insert(0.0) += score * weight;
+                }
+            }
+            if let Some(desc_docs) = index.desc_index.get(syn) {
+                for &doc_id in desc_docs {
+                    token_posts.entry(doc_id).or_insert(0.0);
+                }
+            }
+        }
+
         // Fuzzy fallback (edit-distance <= 1)
         if token_posts.is_empty() && token.len() >= FUZZY_MIN_LEN {
             for key in index.inverted_dict.keys() {
                 if key.len().abs_diff(token.len()) <= 1 && edit_distance(key, token, 1) <= 1 {
                     if let Some(postings) = index.get_postings(key) {
                         for (doc_id, score) in postings {
-                            *token_posts.entry(doc_id).or_insert(0.0) += score * 0.5;
+                            *token_posts.entry(doc_id).or_insert(0.0) +=
+                                score * profile.fuzzy_penalty;
                         }
                     }
                 }
@@ -137,6 +375,31 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
             }
         }
 
+        // Mined associations: a small score boost, and only partial credit
+        // towards coverage for documents reached no other way
+        let mut assoc_posts: HashMap<u32, (f32, f32)> = HashMap::new();
+        for (related, npmi) in index.associations.get(token).map(Vec::as_slice).unwrap_or(&[]) {
+            let weight = npmi * ASSOC_EXPANSION_WEIGHT;
+            if let Some(postings) = index.get_postings(related) {
+                for (doc_id, score) in postings {
+                    let entry = assoc_posts.entry(doc_id).or_insert((0.0, 0.0));
+                    entry.0 += score * weight;
+                    entry.1 = entry.1.max(weight);
+                }
+            }
+        }
+        if let Some(ex) = explanation.as_mut() {
+            let total = token_posts.get(&ex.doc_id).copied().unwrap_or(0.0);
+            ex.add_term(token, tok_idf, total, index);
+            ex.associations += assoc_posts.get(&ex.doc_id).map_or(0.0, |&(s, _)| s);
+        }
+        for (doc_id, (score, weight)) in assoc_posts {
+            *doc_score.entry(doc_id).or_insert(0.0) += score;
+            if !token_posts.contains_key(&doc_id) {
+                *doc_matched_idf.entry(doc_id).or_insert(0.0) += tok_idf * weight;
+            }
+        }
+
         let matched = !token_posts.is_empty();
         for (doc_id, score) in token_posts {
             *doc_score.entry(doc_id).or_insert(0.0) += score;
@@ -146,12 +409,20 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
         }
     }
 
+    // Filters apply before pages are collapsed to one per name, so that
+    // printf.3 is found when printf.1 would have outranked it
+    let wanted = |doc_id: u32| {
+        source_matches(&index.sources[doc_id as usize], sources)
+            && (sections.is_empty()
+                || split_page(&index.doc_map[doc_id as usize])
+                    .is_some_and(|(_, section)| section_matches(section, sections)))
+    };
     let and_exp = (query_tokens_vec.len() as f32 - 1.0).max(2.0);
     let mut candidates: Vec<(u32, f32)> = doc_score
         .into_iter()
         .filter_map(|(doc_id, score)| {
             let midf = *doc_matched_idf.get(&doc_id).unwrap_or(&0.0);
-            if midf == 0.0 {
+            if midf == 0.0 || !wanted(doc_id) {
                 return None;
             }
             let coverage = (midf / total_idf).min(1.0);
@@ -159,78 +430,336 @@ pub fn search(query: &str, index: &MmapIndex) -> Vec<SearchResult> {
         })
         .collect();
 
-    candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    candidates.sort_by(by_score_desc);
+
+    if let Some(ex) = explanation.as_mut() {
+        let midf = doc_matched_idf.get(&ex.doc_id).copied().unwrap_or(0.0);
+        ex.coverage = (midf / total_idf).min(1.0);
+        ex.coverage_factor = ex.coverage.powf(and_exp);
+    }
+
+    // Query and descriptions as IDF-weighted vectors widened by the mined
+    // associations, so related wording still scores in the rerank
+    let mut query_vec = token_idfs.clone();
+    expand_vector(&mut query_vec, &index.associations);
 
+    // Only the top candidates are reranked; the rest follow in BM25 order
+    let rest = candidates.split_off(SEMANTIC_RERANK_N.min(candidates.len()));
     let mut reranked: Vec<(u32, f32)> = candidates
         .into_iter()
-        .take(SEMANTIC_RERANK_N)
         .map(|(doc_id, bm25_score)| {
+            let name_desc = &index.name_descs[doc_id as usize];
             let sem = semantic_desc_score(
                 &query_token_set,
                 &token_idfs,
-                &index.name_descs[doc_id as usize],
+                name_desc,
+                &index.synonyms,
                 &stemmer,
             );
-            (doc_id, bm25_score * (1.0 + SEMANTIC_WEIGHT * sem))
+            let mut desc_vec: HashMap<String, f32> = tokenize(name_desc, &stemmer)
+                .into_iter()
+                .map(|t| {
+                    let idf = query_idf(&t, index, n);
+                    (t, idf)
+                })
+                .collect();
+            expand_vector(&mut desc_vec, &index.associations);
+            let assoc_sim = cosine(&query_vec, &desc_vec);
+            let factor =
+                1.0 + profile.semantic_weight * sem + profile.assoc_rerank_weight * assoc_sim;
+            if let Some(ex) = explanation.as_mut().filter(|ex| ex.doc_id == doc_id) {
+                ex.semantic = sem;
+                ex.assoc_similarity = assoc_sim;
+                ex.rerank_factor = factor;
+            }
+            (doc_id, bm25_score * factor)
         })
         .collect();
 
-    reranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    reranked.sort_by(by_score_desc);
+    reranked.extend(rest);
 
-    let mut best_for_base: HashMap<String, (u32, f32)> = HashMap::new();
-    for &(doc_id, score) in &reranked {
-        let base = index.doc_map[doc_id as usize]
+    #[cfg(feature = "embeddings")]
+    let reranked: Vec<(u32, f32)> = fuse_vectors(query, index, reranked)
+        .into_iter()
+        .filter(|&(doc_id, _)| wanted(doc_id))
+        .collect();
+
+    let base_name = |doc_id: u32| {
+        index.doc_map[doc_id as usize]
             .split('.')
             .next()
             .unwrap_or("")
-            .to_lowercase();
+            .to_lowercase()
+    };
+    let mut best_for_base: HashMap<String, (u32, f32)> = HashMap::new();
+    for &(doc_id, score) in &reranked {
         let entry = best_for_base
-            .entry(base)
+            .entry(base_name(doc_id))
             .or_insert((doc_id, f32::NEG_INFINITY));
         if score > entry.1 {
             *entry = (doc_id, score);
         }
     }
+    // A tldr page is shown through the man page of the same name carrying
+    // its examples when that page matched too, at the better of the two scores
+    for &(doc_id, _) in &reranked {
+        let Some(page) = index.example_pages[doc_id as usize].filter(|&p| p != doc_id) else {
+            continue;
+        };
+        if let Some(entry) = best_for_base.get_mut(&base_name(doc_id)) {
+            if entry.0 == page {
+                entry.0 = doc_id;
+            }
+        }
+    }
 
     let mut deduped: Vec<(u32, f32)> = best_for_base.into_values().collect();
-    deduped.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    deduped.sort_by(by_score_desc);
 
-    deduped
+    let results = deduped
         .into_iter()
         .map(|(doc_id, score)| SearchResult {
             doc_id,
             fname: index.doc_map[doc_id as usize].clone(),
+            source: index.sources[doc_id as usize].clone(),
             name_desc: index.name_descs[doc_id as usize].clone(),
             score,
+            snippet: None,
+            examples: Vec::new(),
+            info_link: None,
         })
-        .collect()
+        .collect();
+    (results, explanation)
 }
 
-pub fn search_and_print(query: &str, index: &MmapIndex, top_k: usize) {
+/// How the CLI prints a `SearchReport`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutputFormat {
+    /// Human-readable results with scores and snippets
+    Text,
+    /// One fname per line
+    Plain,
+    /// `fname<TAB>score<TAB>description<TAB>snippet<TAB>source`, one result per line
+    Tsv,
+    /// The whole report as one JSON object
+    Json,
+    /// One JSON `SearchResult` per line
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "plain" => Ok(OutputFormat::Plain),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(format!(
+                "unknown format `{s}` (expected text, plain, tsv, json or jsonl)"
+            )),
+        }
+    }
+}
+
+/// The top results of one query plus how the query was interpreted.
+#[derive(Serialize)]
+pub struct SearchReport {
+    pub query: String,
+    /// Stemmed terms the query was reduced to.
+    pub tokens: Vec<String>,
+    /// Synonyms the terms were expanded with.
+    pub synonyms: Vec<String>,
+    /// Number of hits before paging.
+    pub total: usize,
+    /// Rank of the first result, counting from 0.
+    pub offset: usize,
+    /// Spelling-corrected query, when a query word looks misspelt.
+    pub suggestion: Option<String>,
+    pub results: Vec<SearchResult>,
+}
+
+/// Which results of a query a `SearchReport` keeps.
+#[derive(Clone)]
+pub struct ReportOptions {
+    pub top_k: usize,
+    /// Results to skip, for paging.
+    pub offset: usize,
+    /// Keep only these sections (`3` also matches `3p`); empty keeps all.
+    pub sections: Vec<String>,
+    /// Keep only pages from these sources, as well as any named by
+    /// `source:` in the query; empty keeps all.
+    pub sources: Vec<String>,
+}
+
+/// Runs `query` and keeps one page of results, with snippets. Long-running
+/// callers pass their `speller`; otherwise one is built when needed.
+pub fn search_report(
+    query: &str,
+    index: &MmapIndex,
+    options: &ReportOptions,
+    speller: Option<&SpellChecker>,
+) -> SearchReport {
     let stemmer = make_stemmer();
-    let tokens = tokenize(query, &stemmer);
+    let text = split_filters(query).0;
+    let tokens = tokenize(&text, &stemmer);
+    let synonyms = tokens
+        .iter()
+        .flat_map(|t| index.synonyms.expand(t))
+        .map(|(syn, _)| syn.clone())
+        .collect();
+
+    let results = search_filtered(query, index, &options.sources, &options.sections);
+    let total = results.len();
+    let mut results: Vec<SearchResult> = results
+        .into_iter()
+        .skip(options.offset)
+        .take(options.top_k)
+        .collect();
+    attach_snippets(&mut results, &text, index);
+    attach_links(&mut results, index);
+
+    let suggestion = if !tokens.is_empty()
+        && (results.is_empty() || tokens.iter().any(|t| index.df(t) < SPELL_KNOWN_DF))
+    {
+        match speller {
+            Some(speller) => speller.correct(&text, index),
+            None => SpellChecker::build(index).correct(&text, index),
+        }
+    } else {
+        None
+    };
+
+    SearchReport {
+        query: query.to_string(),
+        tokens,
+        synonyms,
+        total,
+        offset: options.offset,
+        suggestion,
+        results,
+    }
+}
 
-    println!("\nQuery: '{query}'");
-    println!("  Tokens: {tokens:?}");
+/// Field text safe to put in one TSV column.
+fn tsv_field(s: &str) -> String {
+    s.replace(['\t', '\n', '\r'], " ")
+}
 
-    if tokens.is_empty() {
-        println!("  No searchable terms.");
-        return;
+/// Writes `report` in `format`. `verbose` adds the query/token/synonym
+/// lines to the text format; the other formats never include them.
+pub fn write_report(
+    out: &mut impl Write,
+    report: &SearchReport,
+    format: OutputFormat,
+    verbose: bool,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => write_text(out, report, verbose),
+        OutputFormat::Plain => {
+            for r in &report.results {
+                writeln!(out, "{}", r.fname)?;
+            }
+            Ok(())
+        }
+        OutputFormat::Tsv => {
+            for r in &report.results {
+                let snippet = r.snippet.as_ref().map(|s| s.text.as_str()).unwrap_or("");
+                writeln!(
+                    out,
+                    "{}\t{:.3}\t{}\t{}\t{}",
+                    r.fname,
+                    r.score,
+                    tsv_field(&r.name_desc),
+                    tsv_field(snippet),
+                    r.source
+                )?;
+            }
+            Ok(())
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer(&mut *out, report)?;
+            writeln!(out)
+        }
+        OutputFormat::Jsonl => {
+            for r in &report.results {
+                serde_json::to_writer(&mut *out, r)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
     }
+}
 
-    let results = search(query, index);
+fn write_text(out: &mut impl Write, report: &SearchReport, verbose: bool) -> io::Result<()> {
+    // Later pages continue the listing without repeating the header
+    if verbose && report.offset == 0 {
+        writeln!(out, "\nQuery: '{}'", report.query)?;
+        writeln!(out, "  Tokens: {:?}", report.tokens)?;
+        if !report.synonyms.is_empty() {
+            writeln!(out, "  Synonyms: {:?}", report.synonyms)?;
+        }
+    }
 
-    if results.is_empty() {
-        println!("  No results found.");
-        return;
+    if report.tokens.is_empty() {
+        writeln!(out, "  No searchable terms.")?;
+        return Ok(());
+    }
+    if let Some(corrected) = &report.suggestion {
+        writeln!(out, "  Did you mean: '{corrected}'?")?;
+    }
+    if report.results.is_empty() {
+        writeln!(out, "  No results found.")?;
+        return Ok(());
     }
 
-    for r in results.iter().take(top_k) {
+    let ansi = io::stdout().is_terminal();
+    for (i, r) in report.results.iter().enumerate() {
         let preview = if r.name_desc.is_empty() {
             String::new()
         } else {
             format!(" -> {}", r.name_desc)
         };
-        println!("  [{:.3}] {}{}", r.score, r.fname, preview);
+        let source = if r.source.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", r.source)
+        };
+        let rank = report.offset + i + 1;
+        writeln!(out, "  {rank:>2}. [{:.3}] {}{source}{preview}", r.score, r.fname)?;
+        if let Some(snippet) = &r.snippet {
+            if ansi {
+                writeln!(out, "          {}", snippet.to_ansi())?;
+            } else {
+                writeln!(out, "          {}", snippet.text)?;
+            }
+        }
+        for example in r.examples.iter().take(RESULT_EXAMPLES) {
+            writeln!(out, "          $ {}  # {}", example.command, example.description)?;
+        }
+        if let Some(link) = &r.info_link {
+            let kind = if is_info_page(&r.fname) { "man" } else { "info" };
+            writeln!(out, "          {kind}: {link}")?;
+        }
+    }
+    let shown = report.offset + report.results.len();
+    if verbose && shown < report.total {
+        writeln!(out, "  ({shown} of {} results)", report.total)?;
     }
+    Ok(())
+}
+
+pub fn search_and_print(query: &str, index: &MmapIndex, top_k: usize) {
+    let options = ReportOptions {
+        top_k,
+        offset: 0,
+        sections: Vec::new(),
+        sources: Vec::new(),
+    };
+    let report = search_report(query, index, &options, None);
+    // Nothing useful to do when stdout is gone
+    let _ = write_report(&mut io::stdout().lock(), &report, OutputFormat::Text, true);
 }
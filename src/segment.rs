@@ -0,0 +1,187 @@
+//! Segmented indexes. Each crawl can be saved as its own small index file
+//! (a segment); a segment list names the segments that make up one index,
+//! and `load_index` searches them together. `merge` folds them back into a
+//! single file without the deleted pages.
+//!
+//! Segment list format, one entry per line, oldest first (`#` starts a
+//! comment); paths are relative to the list:
+//!
+//! ```text
+//! segment man.idx
+//! segment vendor-acme.idx
+//! delete acme-legacy.1.gz
+//! ```
+//!
+//! `delete` drops a page from the segments listed before it, and a page in
+//! a later segment replaces the page of the same name in earlier ones.
+//! IDF is computed over the live pages of all segments, but BM25 length
+//! normalisation uses each segment's own average field lengths, also after
+//! a merge; only re-running `index` recomputes it.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::export::{build_from_export, doc_record, sorted_terms, Export, TermRecord};
+use crate::index::{Index, MmapIndex};
+
+#[derive(Clone, PartialEq)]
+pub enum Entry {
+    Segment(String),
+    Delete(String),
+}
+
+#[derive(Default)]
+pub struct SegmentList {
+    pub entries: Vec<Entry>,
+}
+
+impl SegmentList {
+    pub fn read(path: &str) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut entries = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let entry = match line.split_once(char::is_whitespace) {
+                Some(("segment", file)) => Entry::Segment(file.trim().to_string()),
+                Some(("delete", fname)) => Entry::Delete(fname.trim().to_string()),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "{path}:{}: expected `segment <file>` or `delete <fname>` \
+                             (not an index or segment list?)",
+                            i + 1
+                        ),
+                    ))
+                }
+            };
+            entries.push(entry);
+        }
+        Ok(SegmentList { entries })
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut out = String::from("# Segments of one index, oldest first\n");
+        for entry in &self.entries {
+            match entry {
+                Entry::Segment(file) => out += &format!("segment {file}\n"),
+                Entry::Delete(fname) => out += &format!("delete {fname}\n"),
+            }
+        }
+        fs::write(path, out)
+    }
+
+    /// Segment paths, resolved against the directory of the list at
+    /// `list_path`, each with the pages deleted from it.
+    pub fn resolve(&self, list_path: &str) -> Vec<(String, HashSet<String>)> {
+        let dir = Path::new(list_path).parent().unwrap_or(Path::new(""));
+        let mut segments: Vec<(String, HashSet<String>)> = Vec::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::Segment(file) => {
+                    let path = dir.join(file).to_string_lossy().into_owned();
+                    segments.push((path, HashSet::new()));
+                }
+                Entry::Delete(fname) => {
+                    for (_, deleted) in &mut segments {
+                        deleted.insert(fname.clone());
+                    }
+                }
+            }
+        }
+        segments
+    }
+}
+
+/// One index built from the live pages of every segment. Scores keep the
+/// IDF over the live pages and each segment's length normalisation, as
+/// searches over the segments use them; term associations are mined again
+/// for the merged collection. Vectors are not carried over; re-run `index`
+/// with embeddings to get them back.
+pub fn merge(index: &MmapIndex) -> io::Result<Index> {
+    let mut export = Export::default();
+    for doc_id in 0..index.doc_map.len() as u32 {
+        export.docs.push(doc_record(index, doc_id, true));
+    }
+    for term in sorted_terms(index) {
+        let postings = index.get_field_postings(term).unwrap_or_default();
+        if postings.is_empty() {
+            continue;
+        }
+        // Postings already carry the IDF over the live pages
+        export.terms.push(TermRecord {
+            term: term.to_string(),
+            postings,
+        });
+    }
+    build_from_export(export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        let file = format!("{name}-{}", std::process::id());
+        let path = std::env::temp_dir().join(file);
+        path.to_string_lossy().into_owned()
+    }
+
+    fn deleted(fnames: &[&str]) -> HashSet<String> {
+        fnames.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_and_writes_entries() {
+        let path = temp_path("segments-read");
+        let content = "# comment\nsegment man.idx\n\n  delete old.1.gz  # gone\nsegment b.idx\n";
+        fs::write(&path, content).unwrap();
+        let list = SegmentList::read(&path).unwrap();
+        list.write(&path).unwrap();
+        let again = SegmentList::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let expected = [
+            Entry::Segment("man.idx".to_string()),
+            Entry::Delete("old.1.gz".to_string()),
+            Entry::Segment("b.idx".to_string()),
+        ];
+        assert!(list.entries == expected);
+        assert!(again.entries == expected);
+    }
+
+    #[test]
+    fn rejects_unknown_lines() {
+        let path = temp_path("segments-bad");
+        fs::write(&path, "segment man.idx\nremove ls.1\n").unwrap();
+        let error = SegmentList::read(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with(&format!("{path}:2: ")));
+    }
+
+    #[test]
+    fn deletes_apply_to_earlier_segments_only() {
+        let list = SegmentList {
+            entries: vec![
+                Entry::Segment("a.idx".to_string()),
+                Entry::Delete("ls.1".to_string()),
+                Entry::Segment("b.idx".to_string()),
+                Entry::Delete("cp.1".to_string()),
+                Entry::Segment("c.idx".to_string()),
+            ],
+        };
+        let resolved = list.resolve("/data/man.segments");
+        let expected = [
+            ("/data/a.idx".to_string(), deleted(&["ls.1", "cp.1"])),
+            ("/data/b.idx".to_string(), deleted(&["cp.1"])),
+            ("/data/c.idx".to_string(), deleted(&[])),
+        ];
+        assert_eq!(resolved, expected);
+    }
+}
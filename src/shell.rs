@@ -0,0 +1,233 @@
+//! Shell integration: the `search shell-init` snippets (a key binding that
+//! turns the command line into a search and inserts the chosen command, and
+//! a command-not-found hook) and the lookup behind that hook.
+
+use std::cmp::Ordering;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::apropos::{section_matches, AproposEntry};
+use crate::constants::{NOT_FOUND_MAX_SUGGESTIONS, SHELL_WIDGET_RESULTS};
+use crate::doc::is_man_page;
+use crate::graph::split_page;
+use crate::index::MmapIndex;
+use crate::text::{edit_distance, make_stemmer};
+
+/// Sections that document things a user can type at a prompt.
+const COMMAND_SECTIONS: [&str; 2] = ["1", "8"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!("unknown shell `{s}` (expected bash, zsh or fish)")),
+        }
+    }
+}
+
+const BASH_INIT: &str = r#"# man_search shell integration for bash: eval "$(search shell-init bash)"
+__man_search_pick() {
+    if command -v fzf >/dev/null 2>&1; then
+        @SEARCH@ --index @INDEX@ --format tsv --top-k @RESULTS@ -s 1:8 "$1" \
+            | fzf --height 40% --reverse --delimiter '\t' --with-nth 1,3 --select-1
+    else
+        @SEARCH@ --index @INDEX@ --format tsv --top-k 1 -s 1:8 "$1"
+    fi | cut -f1 | sed -E 's/\.(gz|bz2|xz|zst)$//; s/\.[^.]+$//'
+}
+__man_search_widget() {
+    [ -n "$READLINE_LINE" ] || return
+    local picked
+    picked=$(__man_search_pick "$READLINE_LINE")
+    [ -n "$picked" ] || return
+    READLINE_LINE="$picked "
+    READLINE_POINT=${#READLINE_LINE}
+}
+bind -x '"\C-g": __man_search_widget'
+command_not_found_handle() {
+    @SEARCH@ --index @INDEX@ --not-found "$1"
+}
+"#;
+
+const ZSH_INIT: &str = r#"# man_search shell integration for zsh: eval "$(search shell-init zsh)"
+__man_search_pick() {
+    if (( $+commands[fzf] )); then
+        @SEARCH@ --index @INDEX@ --format tsv --top-k @RESULTS@ -s 1:8 "$1" \
+            | fzf --height 40% --reverse --delimiter '\t' --with-nth 1,3 --select-1
+    else
+        @SEARCH@ --index @INDEX@ --format tsv --top-k 1 -s 1:8 "$1"
+    fi | cut -f1 | sed -E 's/\.(gz|bz2|xz|zst)$//; s/\.[^.]+$//'
+}
+man-search-widget() {
+    [[ -n $BUFFER ]] || return
+    local picked
+    picked=$(__man_search_pick "$BUFFER")
+    [[ -n $picked ]] && BUFFER="$picked " && CURSOR=${#BUFFER}
+    zle reset-prompt
+}
+zle -N man-search-widget
+bindkey '^G' man-search-widget
+command_not_found_handler() {
+    @SEARCH@ --index @INDEX@ --not-found "$1"
+}
+"#;
+
+const FISH_INIT: &str = r#"# man_search shell integration for fish: search shell-init fish | source
+function __man_search_pick
+    if type -q fzf
+        @SEARCH@ --index @INDEX@ --format tsv --top-k @RESULTS@ -s 1:8 $argv[1] \
+            | fzf --height 40% --reverse --delimiter \t --with-nth 1,3 --select-1
+    else
+        @SEARCH@ --index @INDEX@ --format tsv --top-k 1 -s 1:8 $argv[1]
+    end | cut -f1 | sed -E 's/\.(gz|bz2|xz|zst)$//; s/\.[^.]+$//'
+end
+function __man_search_widget
+    set -l line (commandline -b)
+    test -n "$line"; or return
+    set -l picked (__man_search_pick "$line")
+    test -n "$picked"; and commandline -r -- "$picked "
+    commandline -f repaint
+end
+bind \cg __man_search_widget
+function fish_command_not_found
+    @SEARCH@ --index @INDEX@ --not-found $argv[1]
+end
+"#;
+
+/// Quotes `s` for any of the supported shells.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// The snippet to evaluate in `shell`'s startup file. `search` and `index`
+/// should be absolute, as the shell may run them from any directory.
+pub fn init_script(shell: Shell, search: &Path, index: &Path) -> String {
+    let template = match shell {
+        Shell::Bash => BASH_INIT,
+        Shell::Zsh => ZSH_INIT,
+        Shell::Fish => FISH_INIT,
+    };
+    template
+        .replace("@SEARCH@", &quote(&search.to_string_lossy()))
+        .replace("@INDEX@", &quote(&index.to_string_lossy()))
+        .replace("@RESULTS@", &SHELL_WIDGET_RESULTS.to_string())
+}
+
+/// Levenshtein distance that also counts one swap of adjacent characters
+/// (`gti` for `git`) as a single edit.
+fn typo_distance(a: &str, b: &str, max_dist: usize) -> usize {
+    let (x, y): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if x.len() == y.len() {
+        let diffs: Vec<usize> = (0..x.len()).filter(|&i| x[i] != y[i]).collect();
+        if let [i, j] = diffs[..] {
+            if j == i + 1 && x[i] == y[j] && x[j] == y[i] {
+                return 1;
+            }
+        }
+    }
+    edit_distance(a, b, max_dist)
+}
+
+/// Commands documented in the index that `typed` was probably meant to be:
+/// pages whose command stems the same (through `cmd_name_index`), then
+/// names within one or two typos, closest and most authoritative first.
+pub fn closest_commands(index: &MmapIndex, typed: &str) -> Vec<AproposEntry> {
+    let typed = typed.rsplit('/').next().unwrap_or(typed).to_lowercase();
+    let sections: Vec<String> = COMMAND_SECTIONS.iter().map(|s| s.to_string()).collect();
+    let max_dist = if typed.chars().count() <= 4 { 1 } else { 2 };
+
+    let stem = make_stemmer().stem(&typed).into_owned();
+    let same_stem = index.cmd_name_index.get(&stem);
+
+    // (distance, not the typed name itself, page)
+    let mut found: Vec<(usize, bool, AproposEntry)> = Vec::new();
+    for (doc_id, fname) in index.doc_map.iter().enumerate() {
+        let Some((name, section)) = split_page(fname).filter(|_| is_man_page(fname)) else {
+            continue;
+        };
+        if !section_matches(section, &sections) {
+            continue;
+        }
+        let lower = name.to_lowercase();
+        let dist = if same_stem.is_some_and(|ids| ids.contains(&(doc_id as u32))) {
+            0
+        } else {
+            typo_distance(&typed, &lower, max_dist)
+        };
+        if dist > max_dist {
+            continue;
+        }
+        found.push((
+            dist,
+            lower != typed,
+            AproposEntry {
+                doc_id: doc_id as u32,
+                name: name.to_string(),
+                section: section.to_string(),
+                description: index.name_descs[doc_id].clone(),
+            },
+        ));
+    }
+
+    found.sort_by(|(da, ia, a), (db, ib, b)| {
+        (da, ia).cmp(&(db, ib)).then_with(|| {
+            index
+                .doc_prior(b.doc_id)
+                .partial_cmp(&index.doc_prior(a.doc_id))
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+    let mut names: Vec<String> = Vec::new();
+    found
+        .into_iter()
+        .map(|(_, _, e)| e)
+        .filter(|e| {
+            let new = !names.contains(&e.name);
+            if new {
+                names.push(e.name.clone());
+            }
+            new
+        })
+        .take(NOT_FOUND_MAX_SUGGESTIONS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_a_transposition_as_one_typo() {
+        assert_eq!(typo_distance("gti", "git", 1), 1);
+        assert_eq!(typo_distance("sl", "ls", 1), 1);
+        assert_eq!(typo_distance("git", "git", 1), 0);
+    }
+
+    #[test]
+    fn counts_other_edits_like_levenshtein() {
+        assert_eq!(typo_distance("grap", "grep", 2), 1);
+        assert_eq!(typo_distance("tig", "git", 2), 2);
+        assert_eq!(typo_distance("mkdri", "mkdir", 2), 1);
+        assert_eq!(typo_distance("mkdr", "mkdir", 2), 1);
+        assert_eq!(typo_distance("cat", "dog", 1), 2);
+    }
+
+    #[test]
+    fn fills_in_quoted_paths() {
+        let search = Path::new("/opt/it's/search");
+        let script = init_script(Shell::Zsh, search, Path::new("/m.idx"));
+        assert!(script.contains(r"'/opt/it'\''s/search' --index '/m.idx' --not-found"));
+        assert!(!script.contains('@'));
+        assert!("tcsh".parse::<Shell>().is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use rust_stemmers::Stemmer;
+use serde::Serialize;
+
+use crate::constants::SNIPPET_WORDS;
+use crate::index::MmapIndex;
+use crate::search::SearchResult;
+use crate::text::{make_stemmer, tokenize};
+
+#[derive(Serialize, Clone)]
+pub struct Snippet {
+    pub text: String,
+    /// (start, end) character offsets of query terms within `text`.
+    pub highlights: Vec<(usize, usize)>,
+}
+
+impl Snippet {
+    /// Renders the snippet for a terminal, with query terms in bold.
+    pub fn to_ansi(&self) -> String {
+        self.render("\x1b[1m", "\x1b[0m")
+    }
+
+    /// Renders the snippet with each highlighted range wrapped in `open` / `close`.
+    pub fn render(&self, open: &str, close: &str) -> String {
+        let mut out = String::with_capacity(self.text.len() + self.highlights.len() * 8);
+        let mut hl = self.highlights.iter().peekable();
+        for (i, c) in self.text.chars().enumerate() {
+            if hl.peek().is_some_and(|&&(start, _)| start == i) {
+                out.push_str(open);
+            }
+            out.push(c);
+            if hl.peek().is_some_and(|&&(_, end)| end == i + 1) {
+                out.push_str(close);
+                hl.next();
+            }
+        }
+        out
+    }
+}
+
+/// Picks the `SNIPPET_WORDS`-word window of `text` covering the most query
+/// weight, or `None` when no query term occurs in the text.
+pub fn make_snippet(
+    text: &str,
+    term_weights: &HashMap<String, f32>,
+    stemmer: &Stemmer,
+) -> Option<Snippet> {
+    // The first line is the man-page header ("LS(1) User Commands LS(1)")
+    let body_start = text.find('\n').map(|p| p + 1).unwrap_or(0);
+
+    // (byte_start, byte_end, matched term)
+    let mut words: Vec<(usize, usize, Option<&str>)> = Vec::new();
+    let mut start = None;
+    for (i, c) in text[body_start..]
+        .char_indices()
+        .chain(std::iter::once((text.len() - body_start, ' ')))
+    {
+        let is_word = c.is_alphanumeric() || c == '-' || c == '_';
+        match (is_word, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                let word = text[body_start + s..body_start + i].to_lowercase();
+                let stem = stemmer.stem(&word);
+                let term = term_weights
+                    .get_key_value(stem.as_ref())
+                    .map(|(k, _)| k.as_str());
+                words.push((body_start + s, body_start + i, term));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Slide a window, scoring each distinct term once plus a little per repeat
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    let mut score = 0.0f32;
+    let mut best: Option<(usize, f32)> = None;
+    for i in 0..words.len() {
+        if let Some(t) = words[i].2 {
+            let c = counts.entry(t).or_insert(0);
+            *c += 1;
+            score += if *c == 1 { term_weights[t] } else { 0.1 };
+        }
+        if i >= SNIPPET_WORDS {
+            if let Some(t) = words[i - SNIPPET_WORDS].2 {
+                let c = counts.get_mut(t).unwrap();
+                *c -= 1;
+                score -= if *c == 0 { term_weights[t] } else { 0.1 };
+            }
+        }
+        if score > 0.0 && best.is_none_or(|(_, s)| score > s + 1e-6) {
+            best = Some((i.saturating_sub(SNIPPET_WORDS - 1), score));
+        }
+    }
+    let (first, _) = best?;
+
+    // Centre the window on the matched words it contains
+    let window = first..(first + SNIPPET_WORDS).min(words.len());
+    let matched: Vec<usize> = window.filter(|&i| words[i].2.is_some()).collect();
+    let mid = (matched[0] + matched[matched.len() - 1]) / 2;
+    let first = mid
+        .saturating_sub(SNIPPET_WORDS / 2)
+        .min(words.len().saturating_sub(SNIPPET_WORDS));
+    let last = (first + SNIPPET_WORDS).min(words.len()) - 1;
+
+    let mut snippet = Snippet {
+        text: String::new(),
+        highlights: Vec::new(),
+    };
+    if first > 0 {
+        snippet.text.push_str("… ");
+    }
+    let base = words[first].0;
+    let base_chars = snippet.text.chars().count();
+    for &(s, e, term) in &words[first..=last] {
+        if term.is_some() {
+            let start = base_chars + text[base..s].chars().count();
+            let end = start + text[s..e].chars().count();
+            snippet.highlights.push((start, end));
+        }
+    }
+    snippet.text.extend(
+        text[base..words[last].1]
+            .chars()
+            .map(|c| if c == '\n' { ' ' } else { c }),
+    );
+    if last + 1 < words.len() {
+        snippet.text.push_str(" …");
+    }
+    Some(snippet)
+}
+
+/// Fills in `snippet` for each result from the page text stored in the index.
+pub fn attach_snippets(results: &mut [SearchResult], query: &str, index: &MmapIndex) {
+    let stemmer = make_stemmer();
+    let n = index.doc_map.len() as f32;
+    let term_weights: HashMap<String, f32> = tokenize(query, &stemmer)
+        .into_iter()
+        .map(|t| {
+            let df = index.df(&t).max(1) as f32;
+            let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln().max(0.01);
+            (t, idf)
+        })
+        .collect();
+    if term_weights.is_empty() {
+        return;
+    }
+
+    for r in results {
+        r.snippet = make_snippet(index.doc_text(r.doc_id), &term_weights, &stemmer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(text: &str, terms: &[&str]) -> Option<Snippet> {
+        let weights = terms.iter().map(|t| (t.to_string(), 1.0)).collect();
+        make_snippet(text, &weights, &make_stemmer())
+    }
+
+    #[test]
+    fn skips_the_header_line() {
+        assert!(snippet("COPY(1) User Commands COPY(1)\nnothing here", &["copi"]).is_none());
+    }
+
+    #[test]
+    fn highlights_stemmed_matches() {
+        let found = snippet("CP(1)\ncp copies files and dirs", &["copi", "file"]).unwrap();
+        assert_eq!(found.render("[", "]"), "cp [copies] [files] and dirs");
+    }
+
+    #[test]
+    fn picks_the_window_with_most_weight() {
+        let filler = "word ".repeat(3 * SNIPPET_WORDS);
+        let text = format!("HEADER\nremove {filler} remove directory {filler} end");
+        let found = snippet(&text, &["remov", "directori"]).unwrap();
+        assert!(found.text.starts_with("… ") && found.text.ends_with(" …"));
+        assert_eq!(found.highlights.len(), 2);
+        assert!(found.render("[", "]").contains("[remove] [directory]"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use rust_stemmers::Stemmer;
+
+use crate::constants::*;
+use crate::index::MmapIndex;
+use crate::text::{edit_distance, is_stop_word, make_stemmer, surface_forms};
+
+/// "Did you mean" corrector over command names and the surface forms of
+/// dictionary terms, preferring close and frequent words.
+pub struct SpellChecker {
+    /// (word, document frequency)
+    words: Vec<(String, u32)>,
+    commands: HashMap<String, u32>,
+    stemmer: Stemmer,
+}
+
+impl SpellChecker {
+    pub fn build(index: &MmapIndex) -> Self {
+        let stemmer = make_stemmer();
+
+        let mut words: HashMap<String, u32> = HashMap::new();
+        for (stem, forms) in surface_forms(&index.name_descs, &stemmer) {
+            let df = index.df(&stem);
+            if df == 0 {
+                continue;
+            }
+            for (form, _) in forms {
+                words.insert(form, df);
+            }
+        }
+
+        let mut commands: HashMap<String, u32> = HashMap::new();
+        for (doc_id, fname) in index.doc_map.iter().enumerate() {
+            let base = fname.split('.').next().unwrap_or("").to_lowercase();
+            if base.len() < 2 {
+                continue;
+            }
+            let df = index.df(&index.cmd_names[doc_id]).max(1);
+            let entry = commands.entry(base.clone()).or_insert(0);
+            *entry = (*entry).max(df);
+            words.entry(base).or_insert(df);
+        }
+
+        SpellChecker {
+            words: words.into_iter().collect(),
+            commands,
+            stemmer,
+        }
+    }
+
+    /// Returns `query` with misspelt words replaced, or `None` if every word
+    /// looks fine or nothing better was found.
+    pub fn correct(&self, query: &str, index: &MmapIndex) -> Option<String> {
+        let mut corrected = String::with_capacity(query.len());
+        let mut changed = false;
+        let mut word = String::new();
+
+        let mut flush = |word: &mut String, out: &mut String| {
+            if let Some(fix) = self.correct_word(word, index) {
+                out.push_str(&fix);
+                changed = true;
+            } else {
+                out.push_str(word);
+            }
+            word.clear();
+        };
+
+        for c in query.chars() {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                word.push(c);
+            } else {
+                flush(&mut word, &mut corrected);
+                corrected.push(c);
+            }
+        }
+        flush(&mut word, &mut corrected);
+
+        changed.then_some(corrected)
+    }
+
+    fn correct_word(&self, word: &str, index: &MmapIndex) -> Option<String> {
+        let lower = word.to_lowercase();
+        let len = lower.chars().count();
+        if len < SPELL_MIN_LEN || lower.starts_with('-') || is_stop_word(&lower) {
+            return None;
+        }
+        if self.commands.contains_key(&lower) {
+            return None;
+        }
+
+        let own_df = index.df(&self.stemmer.stem(&lower));
+        if own_df >= SPELL_KNOWN_DF {
+            return None;
+        }
+
+        let max_dist = if len <= 4 { 1 } else { 2 };
+        let mut best: Option<(&str, f32)> = None;
+        for (cand, df) in &self.words {
+            if *df <= own_df || cand.len().abs_diff(lower.len()) > max_dist {
+                continue;
+            }
+            let dist = edit_distance(&lower, cand, max_dist);
+            if dist == 0 || dist > max_dist {
+                continue;
+            }
+            let score = (*df as f32).ln_1p() - SPELL_DISTANCE_PENALTY * dist as f32;
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((cand, score));
+            }
+        }
+        best.map(|(cand, _)| cand.to_string())
+    }
+}
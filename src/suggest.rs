@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::constants::*;
+use crate::doc::doc_type_multiplier;
+use crate::index::MmapIndex;
+use crate::text::{make_stemmer, surface_forms};
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Command,
+    Term,
+}
+
+#[derive(Serialize)]
+pub struct Suggestion {
+    /// The suggested word itself.
+    pub text: String,
+    /// The whole query with its last word replaced by `text`.
+    pub completion: String,
+    pub kind: SuggestionKind,
+    /// Best-ranked page for command suggestions.
+    pub fname: Option<String>,
+    pub score: f32,
+}
+
+struct Entry {
+    text: String,
+    kind: SuggestionKind,
+    fname: Option<String>,
+    score: f32,
+}
+
+/// Prefix completion over command names and frequent dictionary terms.
+/// Entries are kept sorted by text so a lookup is a binary search plus a
+/// short scan, cheap enough to run on every keystroke.
+pub struct Suggester {
+    entries: Vec<Entry>,
+}
+
+impl Suggester {
+    pub fn build(index: &MmapIndex) -> Self {
+        // Commands: one entry per base name, keeping its best-ranked section
+        let mut commands: HashMap<String, (String, f32)> = HashMap::new();
+        for (doc_id, fname) in index.doc_map.iter().enumerate() {
+            let base = fname.split('.').next().unwrap_or("").to_lowercase();
+            if base.len() < 2 {
+                continue;
+            }
+            let cmd_df = index.df(&index.cmd_names[doc_id]).max(1) as f32;
+            let score = doc_type_multiplier(fname, index.profile()) * (1.0 + cmd_df.ln_1p());
+            let entry = commands
+                .entry(base)
+                .or_insert((fname.clone(), f32::NEG_INFINITY));
+            if score > entry.1 {
+                *entry = (fname.clone(), score);
+            }
+        }
+
+        // Terms: dictionary stems shown through their most common surface
+        // form in NAME descriptions, so users never see "directori".
+        let surfaces = surface_forms(&index.name_descs, &make_stemmer());
+
+        let mut entries: Vec<Entry> = Vec::with_capacity(commands.len() + surfaces.len());
+        for (stem, forms) in surfaces {
+            let term_df = index.df(&stem);
+            if term_df < SUGGEST_MIN_DF {
+                continue;
+            }
+            let Some((text, _)) = forms.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            else {
+                continue;
+            };
+            if commands.contains_key(&text) {
+                continue;
+            }
+            entries.push(Entry {
+                text,
+                kind: SuggestionKind::Term,
+                fname: None,
+                score: SUGGEST_TERM_WEIGHT * (1.0 + (term_df as f32).ln_1p()),
+            });
+        }
+        for (text, (fname, score)) in commands {
+            entries.push(Entry {
+                text,
+                kind: SuggestionKind::Command,
+                fname: Some(fname),
+                score,
+            });
+        }
+
+        entries.sort_by(|a, b| a.text.cmp(&b.text));
+        Suggester { entries }
+    }
+
+    /// Completes the last word of `query`, best matches first.
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<Suggestion> {
+        let lowered = query.trim_start().to_lowercase();
+        let (head, prefix) = match lowered.rfind(char::is_whitespace) {
+            Some(pos) => lowered.split_at(pos + 1),
+            None => ("", lowered.as_str()),
+        };
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.entries.partition_point(|e| e.text.as_str() < prefix);
+        let mut matches: Vec<&Entry> = self.entries[start..]
+            .iter()
+            .take_while(|e| e.text.starts_with(prefix))
+            .collect();
+
+        // An exact hit always comes first, then by score
+        matches.sort_by(|a, b| {
+            (b.text == prefix)
+                .cmp(&(a.text == prefix))
+                .then(b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal))
+                .then(a.text.len().cmp(&b.text.len()))
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|e| Suggestion {
+                text: e.text.clone(),
+                completion: format!("{head}{}", e.text),
+                kind: e.kind,
+                fname: e.fname.clone(),
+                score: e.score,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rust_stemmers::Stemmer;
+
+use crate::constants::{DEFAULT_SYNONYMS, SYNONYM_WEIGHT};
+use crate::text::tokenize;
+
+/// Query-time expansions: stemmed term -> [(stemmed synonym, weight)].
+#[derive(Default)]
+pub struct SynonymTable {
+    map: HashMap<String, Vec<(String, f32)>>,
+}
+
+impl SynonymTable {
+    /// Built-in intents from `DEFAULT_SYNONYMS`, extended by the user file at
+    /// `path` if it exists.
+    ///
+    /// File format, one entry per line (`#` starts a comment):
+    ///
+    /// ```text
+    /// delete = remove, unlink
+    /// folder = directory:0.8
+    /// ```
+    ///
+    /// A `:weight` suffix overrides `SYNONYM_WEIGHT` for that synonym.
+    ///
+    /// A file that cannot be read or parsed is reported on stderr and
+    /// ignored, so a bad edit never stops the index from loading.
+    pub fn load(path: &Path, stemmer: &Stemmer) -> Self {
+        let builtin = || {
+            let mut table = SynonymTable::default();
+            for &(word, synonyms) in DEFAULT_SYNONYMS {
+                for syn in synonyms {
+                    table.insert(word, syn, SYNONYM_WEIGHT, stemmer);
+                }
+            }
+            table
+        };
+        let mut table = builtin();
+        if !path.exists() {
+            return table;
+        }
+        let result = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                table
+                    .extend_from_str(&content, stemmer)
+                    .map_err(|(line, msg)| format!("line {line}: {msg}"))
+            });
+        match result {
+            Ok(()) => table,
+            Err(e) => {
+                eprintln!("Ignoring {}: {e}", path.display());
+                builtin()
+            }
+        }
+    }
+
+    fn extend_from_str(&mut self, content: &str, stemmer: &Stemmer) -> Result<(), (usize, String)> {
+        for (i, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((word, synonyms)) = line.split_once('=') else {
+                return Err((i + 1, "expected `word = synonym, ...`".into()));
+            };
+            for item in synonyms.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                let (syn, weight) = match item.split_once(':') {
+                    Some((syn, w)) => match w.trim().parse::<f32>() {
+                        Ok(w) if w.is_finite() && w >= 0.0 => (syn.trim(), w),
+                        _ => return Err((i + 1, format!("bad weight in `{item}`"))),
+                    },
+                    None => (item, SYNONYM_WEIGHT),
+                };
+                self.insert(word.trim(), syn, weight, stemmer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `word -> synonym`; words that tokenize away (stop-words, very
+    /// short words) are ignored.
+    fn insert(&mut self, word: &str, synonym: &str, weight: f32, stemmer: &Stemmer) {
+        let (Some(word), Some(syn)) = (
+            tokenize(word, stemmer).into_iter().next(),
+            tokenize(synonym, stemmer).into_iter().next(),
+        ) else {
+            return;
+        };
+        if word == syn {
+            return;
+        }
+        let entry = self.map.entry(word).or_default();
+        match entry.iter_mut().find(|(s, _)| *s == syn) {
+            Some(existing) => existing.1 = weight,
+            None => entry.push((syn, weight)),
+        }
+    }
+
+    /// Synonyms of a stemmed query token with their weights.
+    pub fn expand(&self, token: &str) -> &[(String, f32)] {
+        self.map.get(token).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::make_stemmer;
+
+    fn parse(content: &str) -> Result<SynonymTable, (usize, String)> {
+        let mut table = SynonymTable::default();
+        table.extend_from_str(content, &make_stemmer())?;
+        Ok(table)
+    }
+
+    #[test]
+    fn parses_stemmed_entries_with_weights() {
+        let table = parse("# comment\n\nwipe = removing, deleted:0.8  # trailing\n").unwrap();
+        assert_eq!(
+            table.expand("wipe"),
+            [
+                ("remov".to_string(), SYNONYM_WEIGHT),
+                ("delet".to_string(), 0.8)
+            ]
+        );
+    }
+
+    #[test]
+    fn later_entries_override_the_weight() {
+        let table = parse("wipe = remove\nwipe = remove:0.5").unwrap();
+        assert_eq!(table.expand("wipe"), [("remov".to_string(), 0.5)]);
+    }
+
+    #[test]
+    fn skips_stop_words_and_self_synonyms() {
+        let table = parse("wipe = the, wipe").unwrap();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn reports_the_bad_line() {
+        assert_eq!(parse("wipe = remove\nerase").err().unwrap().0, 2);
+        for weight in ["-1", "NaN", "inf", "heavy"] {
+            let content = format!("wipe = remove:{weight}");
+            assert!(parse(&content).is_err(), "{weight}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_builtin_table() {
+        let stemmer = make_stemmer();
+        let builtin = SynonymTable::load(Path::new("/nonexistent/synonyms.txt"), &stemmer);
+        assert!(!builtin.is_empty());
+
+        let path = std::env::temp_dir().join(format!("synonyms-{}.txt", std::process::id()));
+        fs::write(&path, "wipe = remove:-1\n").unwrap();
+        let table = SynonymTable::load(&path, &stemmer);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(table.len(), builtin.len());
+        assert!(table.expand("wipe").is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use rust_stemmers::{Algorithm, Stemmer};
+use std::collections::HashMap;
 
 pub fn make_stemmer() -> Stemmer {
     Stemmer::create(Algorithm::English)
@@ -40,6 +41,35 @@ pub fn tokenize(text: &str, stemmer: &Stemmer) -> Vec<String> {
         .collect()
 }
 
+/// Adds `tokens` to the term frequencies `tf`, returning how many there were.
+pub(crate) fn count_into(tokens: Vec<String>, tf: &mut HashMap<String, u32>) -> u32 {
+    let len = tokens.len() as u32;
+    for t in tokens {
+        *tf.entry(t).or_insert(0) += 1;
+    }
+    len
+}
+
+/// Count the unstemmed spellings of every stem in `texts`,
+/// e.g. "directori" -> {"directory": 3, "directories": 5}.
+pub fn surface_forms<'a>(
+    texts: impl IntoIterator<Item = &'a String>,
+    stemmer: &Stemmer,
+) -> HashMap<String, HashMap<String, u32>> {
+    let mut forms: HashMap<String, HashMap<String, u32>> = HashMap::new();
+    for text in texts {
+        for word in text
+            .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .map(|w| w.trim_matches('-').to_lowercase())
+            .filter(|w| w.len() > 2 && !is_stop_word(w))
+        {
+            let stem = stemmer.stem(&word).into_owned();
+            *forms.entry(stem).or_default().entry(word).or_insert(0) += 1;
+        }
+    }
+    forms
+}
+
 /// Classic Levenshtein distance, bailing out early when `max_dist` is exceeded.
 pub fn edit_distance(a: &str, b: &str, max_dist: usize) -> usize {
     let a: Vec<char> = a.chars().collect();
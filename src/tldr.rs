@@ -0,0 +1,231 @@
+//! tldr-pages (<https://github.com/tldr-pages/tldr>) and other collections
+//! in its markdown format: short pages of example commands, indexed next to
+//! the man pages as `<command>.tldr` and linked to the man pages of the same
+//! command when the index is loaded.
+//!
+//! ```text
+//! # tar
+//!
+//! > Archiving utility.
+//! > More information: <https://www.gnu.org/software/tar>.
+//!
+//! - [c]reate an archive and write it to a [f]ile:
+//!
+//! `tar cf {{path/to/target.tar}} {{path/to/file1 path/to/file2 ...}}`
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use rust_stemmers::Stemmer;
+use serde::{Deserialize, Serialize};
+
+use crate::constants::{TLDR_PLATFORMS, TLDR_SECTION};
+use crate::doc::{normalise_text, DocFields};
+use crate::graph::{split_page, PageRefs};
+use crate::io_util::{read_str, read_u32, write_str, write_u32};
+use crate::text::{count_into, tokenize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Example {
+    /// What the command does, e.g. "Create an archive and write it to a file".
+    pub description: String,
+    /// The command with its `{{placeholder}}` braces removed.
+    pub command: String,
+}
+
+/// True if the crawler should read `path` as a tldr page.
+pub fn is_tldr_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// True if `fname` names a page indexed from tldr markdown.
+pub fn is_tldr_page(fname: &str) -> bool {
+    split_page(fname).is_some_and(|(_, section)| section == TLDR_SECTION)
+}
+
+/// True for directories of a tldr-pages checkout that are not crawled:
+/// translations and platforms outside `TLDR_PLATFORMS`.
+pub fn is_skipped_dir(dir: &Path) -> bool {
+    let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let parent = dir.parent().and_then(Path::file_name).and_then(|n| n.to_str());
+    name.starts_with("pages.") || (parent == Some("pages") && !TLDR_PLATFORMS.contains(&name))
+}
+
+/// Description text without markdown: mnemonic brackets (`[c]reate`),
+/// inline code ticks and link brackets.
+fn plain(s: &str) -> String {
+    s.replace(['[', ']', '`', '<', '>'], "").trim().to_string()
+}
+
+struct Page {
+    description: Vec<String>,
+    examples: Vec<Example>,
+}
+
+/// The `>` description lines and the examples of a page, or `None` if it
+/// has no title or no examples (a README, say).
+fn parse_page(content: &str) -> Option<Page> {
+    let mut titled = false;
+    let mut description = Vec::new();
+    let mut examples = Vec::new();
+    let mut pending: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with("# ") {
+            titled = true;
+        } else if let Some(text) = line.strip_prefix('>') {
+            description.push(plain(text));
+        } else if let Some(text) = line.strip_prefix("- ") {
+            pending = Some(plain(text).trim_end_matches(':').to_string());
+        } else if line.len() > 2 && line.starts_with('`') && line.ends_with('`') {
+            if let Some(description) = pending.take() {
+                let command = line[1..line.len() - 1].replace("{{", "").replace("}}", "");
+                examples.push(Example {
+                    description,
+                    command,
+                });
+            }
+        }
+    }
+    (titled && !examples.is_empty()).then_some(Page {
+        description,
+        examples,
+    })
+}
+
+/// Parses the tldr page at `path` into `DocFields` named after the file:
+/// the first description line is the NAME description, the commands are
+/// the synopsis, and the example descriptions make up the body.
+pub fn parse_tldr_doc(path: &Path, stemmer: &Stemmer) -> Option<DocFields> {
+    let content = fs::read_to_string(path).ok()?;
+    let name = path.file_stem()?.to_str()?.to_lowercase();
+    let page = parse_page(&content)?;
+
+    let name_desc_raw = page.description.first().cloned().unwrap_or_default();
+    let mut name_desc_tf = HashMap::new();
+    let mut synopsis_tf = HashMap::new();
+    let mut body_tf = HashMap::new();
+    let name_desc_len = count_into(tokenize(&name_desc_raw, stemmer), &mut name_desc_tf);
+    let mut synopsis_len = 0;
+    let mut body_len = 0;
+    for line in page.description.iter().skip(1) {
+        if !line.starts_with("More information") {
+            body_len += count_into(tokenize(line, stemmer), &mut body_tf);
+        }
+    }
+    for example in &page.examples {
+        synopsis_len += count_into(tokenize(&example.command, stemmer), &mut synopsis_tf);
+        body_len += count_into(tokenize(&example.description, stemmer), &mut body_tf);
+    }
+
+    let mut text = format!("{name}\n{}\n", page.description.join("\n"));
+    for example in &page.examples {
+        text += &format!("- {}:\n{}\n", example.description, example.command);
+    }
+
+    Some(DocFields {
+        fname: format!("{name}.{TLDR_SECTION}"),
+        cmd_name: stemmer.stem(&name).into_owned(),
+        name_desc_raw,
+        name_desc_tf,
+        name_desc_len,
+        synopsis_tf,
+        synopsis_len,
+        body_tf,
+        body_len,
+        text: normalise_text(&text),
+        refs: PageRefs::default(),
+        examples: page.examples,
+    })
+}
+
+pub fn write_examples<W: Write>(w: &mut W, examples: &[Example]) -> io::Result<()> {
+    write_u32(w, examples.len() as u32)?;
+    for example in examples {
+        write_str(w, &example.description)?;
+        write_str(w, &example.command)?;
+    }
+    Ok(())
+}
+
+pub fn read_examples<R: Read>(r: &mut R) -> io::Result<Vec<Example>> {
+    let len = read_u32(r)? as usize;
+    let mut examples = Vec::with_capacity(len);
+    for _ in 0..len {
+        examples.push(Example {
+            description: read_str(r)?,
+            command: read_str(r)?,
+        });
+    }
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::make_stemmer;
+
+    const TAR: &str = "# tar\n\n> Archiving utility.\n\
+                       > More information: <https://www.gnu.org/software/tar>.\n\n\
+                       - [c]reate an archive and write it to a [f]ile:\n\n\
+                       `tar cf {{path/to/target.tar}} {{path/to/file}}`\n\n\
+                       - List the contents of a tar file:\n\n`tar tvf {{path/to/source.tar}}`\n";
+
+    #[test]
+    fn parses_descriptions_and_examples() {
+        let page = parse_page(TAR).unwrap();
+        assert_eq!(page.description[0], "Archiving utility.");
+        assert_eq!(
+            page.examples[0],
+            Example {
+                description: "create an archive and write it to a file".to_string(),
+                command: "tar cf path/to/target.tar path/to/file".to_string(),
+            }
+        );
+        assert_eq!(page.examples[1].command, "tar tvf path/to/source.tar");
+    }
+
+    #[test]
+    fn skips_pages_without_title_or_examples() {
+        assert!(parse_page("> No title.\n\n- Example:\n\n`cmd`\n").is_none());
+        assert!(parse_page("# readme\n\n> Just prose.\n").is_none());
+        assert!(parse_page("# cmd\n\n`orphan command`\n").is_none());
+    }
+
+    #[test]
+    fn builds_doc_fields_named_after_the_file() {
+        let dir = std::env::temp_dir().join(format!("tldr-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Tar.md");
+        fs::write(&path, TAR).unwrap();
+        let doc = parse_tldr_doc(&path, &make_stemmer());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let doc = doc.unwrap();
+        assert_eq!(doc.fname, "tar.tldr");
+        assert_eq!(doc.name_desc_raw, "Archiving utility.");
+        assert_eq!(doc.synopsis_tf.get("tvf"), Some(&1));
+        assert!(!doc.body_tf.contains_key("gnu"));
+        assert_eq!(doc.examples.len(), 2);
+    }
+
+    #[test]
+    fn skips_translations_and_other_platforms() {
+        assert!(is_skipped_dir(Path::new("tldr/pages.de")));
+        assert!(is_skipped_dir(Path::new("tldr/pages/osx")));
+        assert!(!is_skipped_dir(Path::new("tldr/pages/linux")));
+        assert!(!is_skipped_dir(Path::new("tldr/pages")));
+    }
+
+    #[test]
+    fn round_trips_examples() {
+        let examples = parse_page(TAR).unwrap().examples;
+        let mut buf = Vec::new();
+        write_examples(&mut buf, &examples).unwrap();
+        assert_eq!(read_examples(&mut buf.as_slice()).unwrap(), examples);
+    }
+}
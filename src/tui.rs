@@ -0,0 +1,351 @@
+//! Full-screen search-as-you-type UI: a query box, the live result list and
+//! a preview of the selected page, all served from the local index.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{enable_raw_mode, EnterAlternateScreen};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style as TextStyle};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::constants::TUI_MAX_RESULTS;
+use crate::doc::{is_man_page, render_formatted};
+use crate::graph::split_page;
+use crate::index::MmapIndex;
+use crate::overstrike::{parse_man_formatting, Style};
+use crate::search::{search_filtered, ReportOptions, SearchResult};
+
+const HELP: &str = "↑/↓ select  PgUp/PgDn scroll  Enter open  Ctrl-U clear  Esc quit";
+/// How often the UI checks for finished renders while `man` is running.
+const RENDER_POLL: Duration = Duration::from_millis(50);
+
+/// A page to preview: (doc id, width).
+type PageKey = (u32, u16);
+
+struct App<'a> {
+    index: &'a MmapIndex,
+    sections: Vec<String>,
+    sources: Vec<String>,
+    query: String,
+    /// The query `results` were computed for.
+    searched: Option<String>,
+    results: Vec<SearchResult>,
+    total: usize,
+    selected: ListState,
+    scroll: u16,
+    preview_height: u16,
+    /// Rendered pages by (doc id, width).
+    pages: HashMap<PageKey, Vec<Line<'static>>>,
+    /// Pages `man` is formatting in the background.
+    rendering: HashSet<PageKey>,
+    rendered_tx: Sender<(PageKey, Option<String>)>,
+    rendered_rx: Receiver<(PageKey, Option<String>)>,
+    quit: bool,
+}
+
+/// Runs the UI until the user quits, starting from `query`. Only the
+/// section and source filters of `options` apply; the list shows
+/// `TUI_MAX_RESULTS`.
+pub fn run(index: &MmapIndex, query: &str, options: &ReportOptions) -> io::Result<()> {
+    let (rendered_tx, rendered_rx) = mpsc::channel();
+    let mut app = App {
+        index,
+        sections: options.sections.clone(),
+        sources: options.sources.clone(),
+        query: query.to_string(),
+        searched: None,
+        results: Vec::new(),
+        total: 0,
+        selected: ListState::default(),
+        scroll: 0,
+        preview_height: 0,
+        pages: HashMap::new(),
+        rendering: HashSet::new(),
+        rendered_tx,
+        rendered_rx,
+        quit: false,
+    };
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            if self.searched.as_ref() != Some(&self.query) {
+                self.refresh();
+            }
+            self.receive_pages();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Redraw when a page being formatted is ready
+            if !self.rendering.is_empty() && !event::poll(RENDER_POLL)? {
+                continue;
+            }
+            // Handle everything already queued before searching again, so
+            // fast typing costs one search rather than one per key
+            let event = event::read()?;
+            self.handle(event, terminal)?;
+            while !self.quit && event::poll(Duration::ZERO)? {
+                let event = event::read()?;
+                self.handle(event, terminal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn refresh(&mut self) {
+        let mut results =
+            search_filtered(&self.query, self.index, &self.sources, &self.sections);
+        self.total = results.len();
+        results.truncate(TUI_MAX_RESULTS);
+        self.results = results;
+        self.selected
+            .select((!self.results.is_empty()).then_some(0));
+        self.scroll = 0;
+        self.searched = Some(self.query.clone());
+    }
+
+    fn handle(&mut self, event: Event, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        else {
+            return Ok(());
+        };
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+        match code {
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if ctrl => self.quit = true,
+            KeyCode::Char('u') if ctrl => self.query.clear(),
+            KeyCode::Char('w') if ctrl => {
+                let kept = self.query.trim_end().rfind(' ').map_or(0, |i| i + 1);
+                self.query.truncate(kept);
+            }
+            KeyCode::Char('p') if ctrl => self.select(-1),
+            KeyCode::Char('n') if ctrl => self.select(1),
+            KeyCode::Char(c) if !ctrl => self.query.push(c),
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::PageDown => {
+                self.scroll = self.scroll.saturating_add(self.preview_height.max(2) - 1)
+            }
+            KeyCode::PageUp => {
+                self.scroll = self.scroll.saturating_sub(self.preview_height.max(2) - 1)
+            }
+            KeyCode::Enter => self.open(terminal)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, step: isize) {
+        if self.results.is_empty() {
+            return;
+        }
+        let current = self.selected.selected().unwrap_or(0) as isize;
+        let next = (current + step).clamp(0, self.results.len() as isize - 1);
+        self.selected.select(Some(next as usize));
+        self.scroll = 0;
+    }
+
+    fn selected_doc(&self) -> Option<u32> {
+        self.selected
+            .selected()
+            .and_then(|i| self.results.get(i))
+            .map(|r| r.doc_id)
+    }
+
+    /// Leaves the UI to show the selected page in `man`, then comes back.
+    fn open(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        let Some(doc_id) = self.selected_doc() else {
+            return Ok(());
+        };
+        let fname = &self.index.doc_map[doc_id as usize];
+        // tldr pages and info nodes have no man page, and the preview already
+        // shows all of them
+        let Some((name, section)) = split_page(fname).filter(|_| is_man_page(fname)) else {
+            return Ok(());
+        };
+        ratatui::restore();
+        // A missing `man` just returns to the UI
+        let _ = Command::new("man").arg(section).arg(name).status();
+        let resumed = enable_raw_mode()
+            .and_then(|()| execute!(io::stdout(), EnterAlternateScreen))
+            .and_then(|()| terminal.clear());
+        if resumed.is_err() {
+            // Leave the terminal usable for the error message
+            ratatui::restore();
+        }
+        resumed
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [input, main, status] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, preview] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let mut filters = Vec::new();
+        if !self.sections.is_empty() {
+            filters.push(format!("section {}", self.sections.join(":")));
+        }
+        if !self.sources.is_empty() {
+            filters.push(format!("source {}", self.sources.join(",")));
+        }
+        let title = if filters.is_empty() {
+            " search ".to_string()
+        } else {
+            format!(" search ({}) ", filters.join(", "))
+        };
+        frame.render_widget(
+            Paragraph::new(self.query.as_str())
+                .block(Block::default().borders(Borders::ALL).title(title)),
+            input,
+        );
+        frame.set_cursor_position((input.x + 1 + self.query.chars().count() as u16, input.y + 1));
+
+        let items: Vec<ListItem> = self
+            .results
+            .iter()
+            .map(|r| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        r.fname.clone(),
+                        TextStyle::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw("  "),
+                    Span::styled(
+                        r.name_desc.clone(),
+                        TextStyle::default().add_modifier(Modifier::DIM),
+                    ),
+                ]))
+            })
+            .collect();
+        let list_title = format!(" {} results ", self.total);
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(list_title))
+                .highlight_style(TextStyle::default().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.selected,
+        );
+
+        self.draw_preview(frame, preview);
+        frame.render_widget(
+            Paragraph::new(HELP).style(TextStyle::default().add_modifier(Modifier::DIM)),
+            status,
+        );
+    }
+
+    /// Caches the pages `man` has finished formatting.
+    fn receive_pages(&mut self) {
+        while let Ok((key, raw)) = self.rendered_rx.try_recv() {
+            self.rendering.remove(&key);
+            let lines = match raw {
+                Some(raw) => styled_lines(&raw),
+                None => text_lines(self.index, key.0),
+            };
+            self.pages.insert(key, lines);
+        }
+    }
+
+    /// The cached page, or `None` while `man` formats it on another thread,
+    /// which is started here the first time.
+    fn page(&mut self, key: PageKey) -> Option<&Vec<Line<'static>>> {
+        if !self.pages.contains_key(&key) {
+            let fname = &self.index.doc_map[key.0 as usize];
+            let Some((name, section)) = split_page(fname).filter(|_| is_man_page(fname)) else {
+                self.pages.insert(key, text_lines(self.index, key.0));
+                return self.pages.get(&key);
+            };
+            if self.rendering.insert(key) {
+                let (name, section) = (name.to_string(), section.to_string());
+                let rendered = self.rendered_tx.clone();
+                thread::spawn(move || {
+                    // The UI may have quit meanwhile
+                    let _ = rendered.send((key, render_formatted(&name, &section, key.1)));
+                });
+            }
+        }
+        self.pages.get(&key)
+    }
+
+    fn draw_preview(&mut self, frame: &mut Frame, area: Rect) {
+        self.preview_height = area.height.saturating_sub(2);
+        let block = Block::default().borders(Borders::ALL);
+        let Some(doc_id) = self.selected_doc() else {
+            frame.render_widget(block, area);
+            return;
+        };
+        let index = self.index;
+        let title = match index.sources[doc_id as usize].as_str() {
+            "" => format!(" {} ", index.doc_map[doc_id as usize]),
+            source => format!(" {} ({source}) ", index.doc_map[doc_id as usize]),
+        };
+        let width = area.width.saturating_sub(2);
+        let Some(lines) = self.page((doc_id, width)) else {
+            frame.render_widget(block.title(format!("{title}formatting… ")), area);
+            return;
+        };
+        frame.render_widget(
+            Paragraph::new(lines.clone())
+                .block(block.title(title))
+                .scroll((self.scroll, 0)),
+            area,
+        );
+    }
+}
+
+/// The indexed text of `doc_id`, for pages `man` cannot render.
+fn text_lines(index: &MmapIndex, doc_id: u32) -> Vec<Line<'static>> {
+    index
+        .doc_text(doc_id)
+        .lines()
+        .map(|line| Line::raw(line.to_string()))
+        .collect()
+}
+
+/// `man` output with its overstrike bold and underline as text styles.
+fn styled_lines(raw: &str) -> Vec<Line<'static>> {
+    parse_man_formatting(raw)
+        .into_iter()
+        .map(|runs| {
+            Line::from(
+                runs.into_iter()
+                    .map(|(style, text)| {
+                        let modifier = match style {
+                            Style::Plain => Modifier::empty(),
+                            Style::Bold => Modifier::BOLD,
+                            Style::Underline => Modifier::UNDERLINED,
+                        };
+                        Span::styled(text, TextStyle::default().add_modifier(modifier))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}